@@ -0,0 +1,104 @@
+//! Benchmark of the expansion of the attributes on a large function.
+//!
+//! Run with `cargo bench --bench expansion`. Procedural macros can only be invoked by the
+//! compiler, so each sample type checks a generated crate whose single function contains
+//! `LITERALS` literals. The time of the same crate without an attribute is measured as well, and
+//! subtracted from the other times, so that they approximate the time spent in the expansion.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// Number of literals in the generated function.
+const LITERALS: usize = 5000;
+
+/// Number of timed type checks of each crate, of which the median is reported.
+const SAMPLES: usize = 5;
+
+/// The attributes that are benchmarked, by name, and `None` for the baseline without attribute.
+const CASES: &[(&str, Option<&str>)] = &[
+    ("baseline", None),
+    ("float", Some("replace_float_literals(literal as f64)")),
+    ("numeric", Some("replace_numeric_literals(literal as f64)")),
+    ("nonzero", Some("replace_nonzero_literals(literal as f64)")),
+];
+
+fn source(attribute: Option<&str>) -> String {
+    let mut source = String::from("#![allow(unused_imports)]\nuse numeric_literals::*;\n\n");
+    if let Some(attribute) = attribute {
+        source.push_str(&format!("#[{}]\n", attribute));
+    }
+    source.push_str("pub fn sum(x: f64) -> f64 {\n    let mut sum = 0.0;\n");
+    for i in 0..LITERALS {
+        source.push_str(&format!("    sum += {}.5 * x + {} as f64;\n", i, i % 2));
+    }
+    source.push_str("    sum\n}\n");
+    source
+}
+
+/// Writes a crate with the attribute on the generated function, and returns its directory.
+fn write_crate(root: &Path, name: &str, attribute: Option<&str>) -> PathBuf {
+    let dir = root.join(name);
+    fs::create_dir_all(dir.join("src")).expect("Failed to create benchmark crate");
+    let manifest = format!(
+        "[package]\nname = \"expansion_{}\"\nversion = \"0.0.0\"\nedition = \"2018\"\n\n\
+         [dependencies]\nnumeric_literals = {{ path = {:?} }}\n\n[workspace]\n",
+        name,
+        env!("CARGO_MANIFEST_DIR")
+    );
+    fs::write(dir.join("Cargo.toml"), manifest).expect("Failed to write manifest");
+    fs::write(dir.join("src").join("lib.rs"), source(attribute)).expect("Failed to write source");
+    dir
+}
+
+/// Type checks the crate, after touching its source so that it is checked again.
+fn check(dir: &Path, target: &Path) -> Duration {
+    let lib = dir.join("src").join("lib.rs");
+    let source = fs::read_to_string(&lib).expect("Failed to read source");
+    fs::write(&lib, source).expect("Failed to touch source");
+    let start = Instant::now();
+    let status = Command::new(env::var("CARGO").unwrap_or_else(|_| String::from("cargo")))
+        .args(["check", "--quiet"])
+        .current_dir(dir)
+        .env("CARGO_TARGET_DIR", target)
+        .status()
+        .expect("Failed to run cargo");
+    let elapsed = start.elapsed();
+    assert!(
+        status.success(),
+        "Benchmark crate in {:?} failed to compile",
+        dir
+    );
+    elapsed
+}
+
+fn main() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("target")
+        .join("expansion-bench");
+    let target = root.join("target");
+    let mut baseline = None;
+    for (name, attribute) in CASES {
+        let dir = write_crate(&root, name, *attribute);
+        // The first check also builds the dependencies, so it is not timed
+        check(&dir, &target);
+        let mut samples: Vec<Duration> = (0..SAMPLES).map(|_| check(&dir, &target)).collect();
+        samples.sort();
+        let median = samples[SAMPLES / 2];
+        match baseline {
+            None => {
+                println!("{:<10} {:>8.3} s", name, median.as_secs_f64());
+                baseline = Some(median);
+            }
+            Some(baseline) => println!(
+                "{:<10} {:>8.3} s ({:.3} s over the baseline, with {} literals)",
+                name,
+                median.as_secs_f64(),
+                median.saturating_sub(baseline).as_secs_f64(),
+                2 * LITERALS
+            ),
+        }
+    }
+}
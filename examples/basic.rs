@@ -31,17 +31,30 @@ where
     Ok(res)
 }
 
-#[replace_numeric_literals(f32::from(literal))]
-pub trait A {}
+#[replace_numeric_literals(T::from(literal))]
+pub trait A<T>
+where
+    T: From<i8> + Div<Output = T>,
+{
+    fn half() -> T {
+        1 / 2
+    }
+}
 
-#[allow(dead_code)]
 struct B;
 
+impl A<f64> for B {}
+
 #[replace_numeric_literals(literal)]
-impl B {}
+impl B {
+    fn quarter() -> f64 {
+        Self::half() / 2.0
+    }
+}
 
 fn main() {
     println!("{}", test::<f64>());
     println!("{}", test2::<f64>());
     println!("{}", test3::<f64>().unwrap());
+    println!("{}", B::quarter());
 }
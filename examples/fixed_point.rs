@@ -0,0 +1,41 @@
+//! Fixed-point arithmetic with the `fixed` crate.
+//!
+//! Float literals are converted with `from_num`, which panics if the value does not fit. The
+//! negation in `-1.5` is not part of the literal, so `1.5` is converted and then negated. This
+//! means that the minimum value `-32768.0` of `FixedI32<U16>` can not be written as a literal,
+//! since `32768.0` does not fit. Use `FixedI32::<U16>::MIN` instead.
+extern crate fixed;
+extern crate numeric_literals;
+
+use fixed::types::extra::U16;
+use fixed::FixedI32;
+use numeric_literals::replace_float_literals;
+
+/// Approximates the sine of `x` for `x` in `[-pi/2, pi/2]` with a Taylor polynomial.
+#[replace_float_literals(FixedI32::<U16>::from_num(literal))]
+fn sin_approx(x: FixedI32<U16>) -> FixedI32<U16> {
+    let x2 = x * x;
+    x * (1.0 - x2 / 6.0 * (1.0 - x2 / 20.0))
+}
+
+/// Limits the value to the range `[-1.5, 1.5]`.
+#[replace_float_literals(FixedI32::<U16>::from_num(literal))]
+fn saturate(x: FixedI32<U16>) -> FixedI32<U16> {
+    x.clamp(-1.5, 1.5)
+}
+
+fn main() {
+    for &x in &[-1.5, -0.5, 0.0, 0.5, 1.5] {
+        let x = FixedI32::<U16>::from_num(x);
+        println!(
+            "sin_approx({}) = {} (f64: {})",
+            x,
+            sin_approx(x),
+            f64::sin(x.to_num())
+        );
+    }
+    for &x in &[-3.0, 0.25, 3.0] {
+        let x = FixedI32::<U16>::from_num(x);
+        println!("saturate({}) = {}", x, saturate(x));
+    }
+}
@@ -0,0 +1,38 @@
+//! Generic matrix code with `nalgebra`.
+//!
+//! Only float literals are replaced, so that integer literals remain available for indexing.
+extern crate nalgebra;
+extern crate numeric_literals;
+
+use nalgebra::{Matrix3, RealField, Vector2};
+use numeric_literals::replace_float_literals;
+
+/// The mass matrix of a linear triangle element with unit density.
+#[replace_float_literals(T::from_f64(literal).expect("Literal must fit in T"))]
+fn triangle_mass<T: RealField + Copy>(vertices: &[Vector2<T>; 3]) -> Matrix3<T> {
+    let [a, b, c] = vertices;
+    let (ab, ac) = (b - a, c - a);
+    let area = 0.5 * (ab[0] * ac[1] - ab[1] * ac[0]).abs();
+    #[rustfmt::skip]
+    let mass = Matrix3::new(
+        2.0, 1.0, 1.0,
+        1.0, 2.0, 1.0,
+        1.0, 1.0, 2.0,
+    );
+    mass * (area / 12.0)
+}
+
+fn main() {
+    let vertices = [
+        Vector2::new(0.0, 0.0),
+        Vector2::new(2.0, 0.0),
+        Vector2::new(0.0, 1.0),
+    ];
+    let mass = triangle_mass::<f64>(&vertices);
+    println!("Mass matrix (f64): {}", mass);
+    println!("Total mass: {}", mass.sum());
+
+    let vertices = vertices.map(|v| v.cast::<f32>());
+    let mass = triangle_mass(&vertices);
+    println!("Diagonal entry (f32): {}", mass[(0, 0)]);
+}
@@ -0,0 +1,28 @@
+//! Generic numeric code with the traits of `num-traits`, which are re-exported by `num`.
+//!
+//! Float literals are converted with `NumCast`, while integer literals are converted
+//! relative to `Zero::zero()`.
+extern crate num;
+extern crate numeric_literals;
+
+use num::{Float, NumCast};
+use numeric_literals::{replace_float_literals, replace_int_literals};
+
+/// Smooth Hermite interpolation between 0 and 1.
+#[replace_float_literals(<T as NumCast>::from(literal).unwrap())]
+#[replace_int_literals(T::zero() + <T as NumCast>::from(literal).unwrap())]
+fn smoothstep<T: Float + NumCast>(x: T) -> T {
+    let t = x.max(0.0).min(1.0);
+    t * t * (3 - 2 * t)
+}
+
+fn main() {
+    for &x in &[-0.5, 0.0, 0.25, 0.5, 0.75, 1.0, 1.5] {
+        println!(
+            "smoothstep({}) = {} (f32), {} (f64)",
+            x,
+            smoothstep(x as f32),
+            smoothstep::<f64>(x)
+        );
+    }
+}
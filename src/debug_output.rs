@@ -0,0 +1,69 @@
+//! Machine-readable output of the replaced literals, for tools such as editor extensions.
+//!
+//! With the `debug-output` feature enabled and the environment variable `NUMERIC_LITERALS_DEBUG`
+//! set to `1` for the compiler, every replaced literal is reported on the standard error of the
+//! compiler as a single line of JSON, such as
+//!
+//! ```text
+//! {"kind":"float","literal":"3.14","replacement":"T :: from_f64 (3.14) . unwrap ()","span":{"col":8,"end_col":12,"end_line":42,"line":42}}
+//! ```
+//!
+//! Lines and columns are counted from 1, like in the messages of the compiler. The end of the
+//! span is exclusive.
+
+use proc_macro2::Span;
+use quote::ToTokens;
+use syn::{Expr, ExprLit};
+
+/// The environment variable that enables the output.
+const ENABLE_VARIABLE: &str = "NUMERIC_LITERALS_DEBUG";
+
+/// Returns whether the output is enabled for this invocation of the compiler.
+pub fn is_enabled() -> bool {
+    matches!(std::env::var(ENABLE_VARIABLE), Ok(value) if value == "1")
+}
+
+/// Reports the instantiated replacement of the literal, where `kind` is `float` or `integer`.
+pub fn report(literal: &ExprLit, kind: &str, replacement: &Expr) {
+    eprintln!("{}", record(literal, kind, replacement));
+}
+
+fn record(literal: &ExprLit, kind: &str, replacement: &Expr) -> serde_json::Value {
+    serde_json::json!({
+        "literal": literal.lit.to_token_stream().to_string(),
+        "kind": kind,
+        "replacement": replacement.to_token_stream().to_string(),
+        "span": location(literal.lit.span()),
+    })
+}
+
+fn location(span: Span) -> serde_json::Value {
+    let (start, end) = (span.start(), span.end());
+    serde_json::json!({
+        "line": start.line,
+        "col": start.column + 1,
+        "end_line": end.line,
+        "end_col": end.column + 1,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::record;
+    use syn::{Expr, ExprLit};
+
+    #[test]
+    fn records_literal_replacement_and_location() {
+        let literal: ExprLit = syn::parse_str("\n    3.14").unwrap();
+        let replacement: Expr = syn::parse_str("T::from_f64(3.14).unwrap()").unwrap();
+        assert_eq!(
+            record(&literal, "float", &replacement),
+            serde_json::json!({
+                "literal": "3.14",
+                "kind": "float",
+                "replacement": "T :: from_f64 (3.14) . unwrap ()",
+                "span": { "line": 2, "col": 5, "end_line": 2, "end_col": 9 },
+            })
+        );
+    }
+}
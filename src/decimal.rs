@@ -0,0 +1,543 @@
+//! Exact decimal representation of numeric literals.
+//!
+//! Some placeholders need to know the exact value written in the source rather than the
+//! (possibly rounded) value of the literal as a primitive type. This module parses the
+//! base 10 digits of a literal into an exact decimal representation. All features that
+//! work with the text of a literal should go through this module, so that spellings such as
+//! `2.`, `1e-3` and `1_0e1_0` are treated consistently.
+
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use syn::Lit;
+
+/// A non-negative decimal number represented exactly as `digits * 10^exponent`.
+///
+/// The representation is normalized: `digits` has no leading or trailing zeros,
+/// and zero is represented by the digits `"0"` with an exponent of zero.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decimal {
+    digits: String,
+    exponent: i64,
+}
+
+/// Reasons why a decimal cannot be converted to a scaled integer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScaleError {
+    /// The decimal has more fractional digits than the scale allows.
+    TooManyFractionalDigits { fractional_digits: u64 },
+    /// The scaled value does not fit in 128 bits.
+    Overflow,
+}
+
+/// Binary floating point formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatFormat {
+    F32,
+    F64,
+}
+
+impl FloatFormat {
+    /// Number of significant bits, including the implicit leading bit.
+    fn mantissa_bits(self) -> u64 {
+        match self {
+            FloatFormat::F32 => 24,
+            FloatFormat::F64 => 53,
+        }
+    }
+
+    /// Exponent of the least significant bit of the smallest subnormal number.
+    fn min_exponent(self) -> i64 {
+        match self {
+            FloatFormat::F32 => -149,
+            FloatFormat::F64 => -1074,
+        }
+    }
+
+    /// Exponent of the most significant bit of the largest finite number.
+    fn max_exponent(self) -> i64 {
+        match self {
+            FloatFormat::F32 => 127,
+            FloatFormat::F64 => 1023,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FloatFormat::F32 => "f32",
+            FloatFormat::F64 => "f64",
+        }
+    }
+}
+
+impl Decimal {
+    /// Parses the base 10 digits of an integer or float literal.
+    ///
+    /// Returns `None` if the literal is not numeric.
+    pub fn from_lit(lit: &Lit) -> Option<Self> {
+        match lit {
+            Lit::Int(lit) => Self::parse(lit.base10_digits()),
+            Lit::Float(lit) => Self::parse(lit.base10_digits()),
+            _ => None,
+        }
+    }
+
+    /// Parses decimal text of the form `123`, `1.25`, `2.`, `1.5e-3` or `1_0e1_0`, without suffix.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.replace('_', "");
+        let (mantissa, exponent) = match text.find(['e', 'E']) {
+            Some(pos) => (&text[..pos], text[pos + 1..].parse::<i64>().ok()?),
+            None => (text.as_str(), 0),
+        };
+        let (integral, fractional) = match mantissa.find('.') {
+            Some(pos) => (&mantissa[..pos], &mantissa[pos + 1..]),
+            None => (mantissa, ""),
+        };
+        let all_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+        if integral.is_empty() || !all_digits(integral) || !all_digits(fractional) {
+            return None;
+        }
+
+        let digits = format!("{}{}", integral, fractional);
+        let exponent = exponent.checked_sub(fractional.len() as i64)?;
+        Some(Self::normalized(&digits, exponent))
+    }
+
+    fn normalized(digits: &str, exponent: i64) -> Self {
+        let digits = digits.trim_start_matches('0');
+        let trimmed = digits.trim_end_matches('0');
+        if trimmed.is_empty() {
+            return Decimal {
+                digits: String::from("0"),
+                exponent: 0,
+            };
+        }
+        let trailing_zeros = (digits.len() - trimmed.len()) as i64;
+        Decimal {
+            digits: trimmed.to_string(),
+            exponent: exponent + trailing_zeros,
+        }
+    }
+
+    /// Returns the value in plain decimal notation with at least one fractional digit,
+    /// e.g. `2.0` for `2.` and `0.001` for `1e-3`.
+    pub fn to_float_text(&self) -> String {
+        if self.exponent >= 0 {
+            let zeros = "0".repeat(self.exponent as usize);
+            return format!("{}{}.0", self.digits, zeros);
+        }
+
+        let fractional_digits = self.exponent.unsigned_abs() as usize;
+        if fractional_digits < self.digits.len() {
+            let (integral, fractional) =
+                self.digits.split_at(self.digits.len() - fractional_digits);
+            format!("{}.{}", integral, fractional)
+        } else {
+            let zeros = "0".repeat(fractional_digits - self.digits.len());
+            format!("0.{}{}", zeros, self.digits)
+        }
+    }
+
+    /// Returns the exact value of a finite, non-negative float.
+    pub fn from_f64(value: f64) -> Self {
+        let bits = value.to_bits();
+        let biased_exponent = ((bits >> 52) & 0x7ff) as i64;
+        let fraction = bits & ((1 << 52) - 1);
+        let (mantissa, exponent) = if biased_exponent == 0 {
+            (fraction, -1074)
+        } else {
+            (fraction | (1 << 52), biased_exponent - 1075)
+        };
+
+        // m * 2^e equals m * 2^e * 10^0 for e >= 0, and m * 5^-e * 10^e otherwise
+        let mut digits = BigUint::from_u64(mantissa);
+        let (factor, count) = if exponent >= 0 {
+            (2, exponent)
+        } else {
+            (5, -exponent)
+        };
+        for _ in 0..count {
+            digits.mul_add_small(factor, 0);
+        }
+        Self::normalized(&digits.to_decimal_digits(), exponent.min(0))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits == "0"
+    }
+
+    pub fn is_one(&self) -> bool {
+        self.digits == "1" && self.exponent == 0
+    }
+
+    /// Returns the value as `n * 2^e`, or `None` if it has no finite binary representation.
+    fn to_binary(&self) -> Option<(BigUint, i64)> {
+        let mut n = BigUint::from_decimal_digits(&self.digits);
+        if self.exponent >= 0 {
+            // d * 10^k = d * 5^k * 2^k
+            for _ in 0..self.exponent {
+                n.mul_add_small(5, 0);
+            }
+        } else {
+            // d * 10^-k = (d / 5^k) * 2^-k, which is only finite in binary if 5^k divides d
+            for _ in 0..-self.exponent {
+                let (quotient, remainder) = n.div_rem_small(5);
+                if remainder != 0 {
+                    return None;
+                }
+                n = quotient;
+            }
+        }
+        Some((n, self.exponent))
+    }
+
+    /// Determines whether the value can be represented exactly in the given float format.
+    pub fn is_exactly_representable(&self, format: FloatFormat) -> bool {
+        if self.is_zero() {
+            return true;
+        }
+        // Values far outside the range of f64 would otherwise require needlessly large computations
+        let magnitude = self.digits.len() as i64 + self.exponent;
+        if self.exponent > 400 || magnitude < -400 {
+            return false;
+        }
+
+        match self.to_binary() {
+            None => false,
+            Some((mut n, exponent)) => {
+                let trailing_zeros = n.trailing_zeros();
+                n.shr(trailing_zeros);
+                let exponent = exponent + trailing_zeros as i64;
+                let bits = n.bit_len();
+                bits <= format.mantissa_bits()
+                    && exponent >= format.min_exponent()
+                    && exponent + bits as i64 - 1 <= format.max_exponent()
+            }
+        }
+    }
+
+    /// Returns the value of the float nearest to this value in the given format,
+    /// or `None` if it is out of range.
+    pub fn nearest(&self, format: FloatFormat) -> Option<Self> {
+        let text = self.to_float_text();
+        let value = match format {
+            FloatFormat::F32 => f64::from(text.parse::<f32>().ok()?),
+            FloatFormat::F64 => text.parse::<f64>().ok()?,
+        };
+        if value.is_finite() {
+            Some(Self::from_f64(value))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the value multiplied by `10^scale` as an integer.
+    pub fn scaled(&self, scale: u32) -> Result<u128, ScaleError> {
+        let exponent = self.exponent + i64::from(scale);
+        if exponent < 0 {
+            return Err(ScaleError::TooManyFractionalDigits {
+                fractional_digits: exponent.unsigned_abs() + u64::from(scale),
+            });
+        }
+        let digits = self
+            .digits
+            .parse::<u128>()
+            .map_err(|_| ScaleError::Overflow)?;
+        u32::try_from(exponent)
+            .ok()
+            .and_then(|exponent| 10u128.checked_pow(exponent))
+            .and_then(|factor| digits.checked_mul(factor))
+            .ok_or(ScaleError::Overflow)
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.is_zero(), other.is_zero()) {
+            (true, true) => return Ordering::Equal,
+            (true, false) => return Ordering::Less,
+            (false, true) => return Ordering::Greater,
+            (false, false) => {}
+        }
+        // The position of the most significant digit determines the order of magnitude.
+        // Digits without trailing zeros then compare lexicographically.
+        let magnitude = |d: &Self| d.digits.len() as i64 + d.exponent;
+        magnitude(self)
+            .cmp(&magnitude(other))
+            .then_with(|| self.digits.cmp(&other.digits))
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Minimal arbitrary-precision unsigned integer, sufficient for exact conversions
+/// between decimal and binary representations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BigUint {
+    /// Little-endian 32-bit limbs without trailing zero limbs
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    fn from_u64(value: u64) -> Self {
+        let mut n = BigUint {
+            limbs: vec![value as u32, (value >> 32) as u32],
+        };
+        n.trim();
+        n
+    }
+
+    fn from_decimal_digits(digits: &str) -> Self {
+        let mut n = BigUint { limbs: Vec::new() };
+        for digit in digits.bytes() {
+            n.mul_add_small(10, u32::from(digit - b'0'));
+        }
+        n
+    }
+
+    fn trim(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// Computes `self * factor + addend`.
+    fn mul_add_small(&mut self, factor: u32, addend: u32) {
+        let mut carry = u64::from(addend);
+        for limb in &mut self.limbs {
+            let product = u64::from(*limb) * u64::from(factor) + carry;
+            *limb = product as u32;
+            carry = product >> 32;
+        }
+        if carry != 0 {
+            self.limbs.push(carry as u32);
+        }
+    }
+
+    /// Returns the quotient and remainder of the division by `divisor`.
+    fn div_rem_small(&self, divisor: u32) -> (BigUint, u32) {
+        let mut quotient = vec![0; self.limbs.len()];
+        let mut remainder = 0u64;
+        for (i, limb) in self.limbs.iter().enumerate().rev() {
+            let current = (remainder << 32) | u64::from(*limb);
+            quotient[i] = (current / u64::from(divisor)) as u32;
+            remainder = current % u64::from(divisor);
+        }
+        let mut quotient = BigUint { limbs: quotient };
+        quotient.trim();
+        (quotient, remainder as u32)
+    }
+
+    fn trailing_zeros(&self) -> u64 {
+        let mut zeros = 0;
+        for limb in &self.limbs {
+            if *limb == 0 {
+                zeros += 32;
+            } else {
+                return zeros + u64::from(limb.trailing_zeros());
+            }
+        }
+        zeros
+    }
+
+    fn bit_len(&self) -> u64 {
+        match self.limbs.last() {
+            Some(last) => 32 * self.limbs.len() as u64 - u64::from(last.leading_zeros()),
+            None => 0,
+        }
+    }
+
+    fn shr(&mut self, shift: u64) {
+        let limb_shift = (shift / 32) as usize;
+        let bit_shift = (shift % 32) as u32;
+        self.limbs.drain(..limb_shift.min(self.limbs.len()));
+        if bit_shift > 0 {
+            for i in 0..self.limbs.len() {
+                let high = self
+                    .limbs
+                    .get(i + 1)
+                    .map_or(0, |next| next << (32 - bit_shift));
+                self.limbs[i] = (self.limbs[i] >> bit_shift) | high;
+            }
+        }
+        self.trim();
+    }
+
+    fn to_decimal_digits(&self) -> String {
+        if self.is_zero() {
+            return String::from("0");
+        }
+        let mut digits = Vec::new();
+        let mut n = self.clone();
+        while !n.is_zero() {
+            let (quotient, remainder) = n.div_rem_small(10);
+            digits.push(b'0' + remainder as u8);
+            n = quotient;
+        }
+        digits.reverse();
+        String::from_utf8(digits).expect("Digits must be valid UTF-8")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Decimal, FloatFormat, ScaleError};
+
+    fn float_text(text: &str) -> String {
+        Decimal::parse(text).unwrap().to_float_text()
+    }
+
+    #[test]
+    fn normalizes_trailing_dot() {
+        assert_eq!(float_text("2."), "2.0");
+        assert_eq!(float_text("0."), "0.0");
+    }
+
+    #[test]
+    fn expands_exponents() {
+        assert_eq!(float_text("1e-3"), "0.001");
+        assert_eq!(float_text("1.5E+3"), "1500.0");
+        assert_eq!(float_text("12.5e-1"), "1.25");
+        assert_eq!(float_text("0e10"), "0.0");
+    }
+
+    #[test]
+    fn tolerates_underscores() {
+        assert_eq!(float_text("1_0e1_0"), "100000000000.0");
+        assert_eq!(float_text("1_000.000_5"), "1000.0005");
+    }
+
+    #[test]
+    fn normalizes_redundant_zeros() {
+        assert_eq!(float_text("007.50"), "7.5");
+        assert_eq!(float_text("0.000"), "0.0");
+        assert_eq!(float_text("100"), "100.0");
+    }
+
+    #[test]
+    fn rejects_malformed_text() {
+        assert_eq!(Decimal::parse(""), None);
+        assert_eq!(Decimal::parse(".5"), None);
+        assert_eq!(Decimal::parse("1e"), None);
+        assert_eq!(Decimal::parse("0x10"), None);
+    }
+
+    fn is_exact(text: &str, format: FloatFormat) -> bool {
+        Decimal::parse(text)
+            .unwrap()
+            .is_exactly_representable(format)
+    }
+
+    #[test]
+    fn detects_exactly_representable_values() {
+        for format in [FloatFormat::F32, FloatFormat::F64] {
+            assert!(is_exact("0.5", format));
+            assert!(is_exact("3.0", format));
+            assert!(is_exact("1.25", format));
+            assert!(is_exact("0", format));
+            assert!(is_exact("16777216", format));
+            assert!(!is_exact("0.1", format));
+            assert!(!is_exact("1e-3", format));
+            assert!(!is_exact("1e400", format));
+        }
+        assert!(!is_exact("16777217", FloatFormat::F32));
+        assert!(is_exact("16777217", FloatFormat::F64));
+        assert!(!is_exact("1e39", FloatFormat::F32));
+        assert!(is_exact("1e22", FloatFormat::F64));
+        assert!(!is_exact("1e23", FloatFormat::F64));
+    }
+
+    #[test]
+    fn detects_exactly_representable_subnormals() {
+        let min_subnormal_f32 = "1.40129846432481707092372958328991613128026194187651577175706828388979108268586060148663818836212158203125e-45";
+        assert!(is_exact(min_subnormal_f32, FloatFormat::F32));
+        assert!(!is_exact("0.7e-45", FloatFormat::F32));
+
+        let min_positive_f64 = Decimal::from_f64(f64::MIN_POSITIVE).to_float_text();
+        assert!(is_exact(&min_positive_f64, FloatFormat::F64));
+        assert!(!is_exact(&min_positive_f64, FloatFormat::F32));
+    }
+
+    #[test]
+    fn computes_nearest_representable_value() {
+        let nearest = |text: &str, format| {
+            Decimal::parse(text)
+                .unwrap()
+                .nearest(format)
+                .map(|nearest| nearest.to_float_text())
+        };
+        assert_eq!(
+            nearest("0.1", FloatFormat::F32).as_deref(),
+            Some("0.100000001490116119384765625")
+        );
+        assert_eq!(
+            nearest("0.1", FloatFormat::F64).as_deref(),
+            Some("0.1000000000000000055511151231257827021181583404541015625")
+        );
+        assert_eq!(nearest("2.5", FloatFormat::F32).as_deref(), Some("2.5"));
+        assert_eq!(nearest("1e39", FloatFormat::F32), None);
+    }
+
+    #[test]
+    fn scales_fractional_literals() {
+        assert_eq!(Decimal::parse("1.25").unwrap().scaled(4), Ok(12500));
+        assert_eq!(Decimal::parse("1.2500").unwrap().scaled(2), Ok(125));
+        assert_eq!(Decimal::parse("0.0001").unwrap().scaled(4), Ok(1));
+        assert_eq!(Decimal::parse("0.0").unwrap().scaled(4), Ok(0));
+    }
+
+    #[test]
+    fn scales_exponent_literals() {
+        assert_eq!(Decimal::parse("1e-3").unwrap().scaled(4), Ok(10));
+        assert_eq!(Decimal::parse("1.5e3").unwrap().scaled(0), Ok(1500));
+        assert_eq!(Decimal::parse("12e-1").unwrap().scaled(1), Ok(12));
+    }
+
+    #[test]
+    fn rejects_excess_fractional_digits() {
+        assert_eq!(
+            Decimal::parse("1.23456").unwrap().scaled(4),
+            Err(ScaleError::TooManyFractionalDigits {
+                fractional_digits: 5
+            })
+        );
+        assert_eq!(
+            Decimal::parse("1e-5").unwrap().scaled(2),
+            Err(ScaleError::TooManyFractionalDigits {
+                fractional_digits: 5
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_overflow() {
+        assert_eq!(
+            Decimal::parse("1e40").unwrap().scaled(0),
+            Err(ScaleError::Overflow)
+        );
+        assert_eq!(
+            Decimal::parse("340282366920938463463374607431768211455")
+                .unwrap()
+                .scaled(1),
+            Err(ScaleError::Overflow)
+        );
+    }
+
+    #[test]
+    fn compares_values_exactly() {
+        let decimal = |text| Decimal::parse(text).unwrap();
+        assert!(decimal("0") < decimal("1e-400"));
+        assert!(decimal("0.1") < decimal("1"));
+        assert!(decimal("12") < decimal("12.01"));
+        assert!(decimal("99") < decimal("100"));
+        assert!(decimal("1.5") > decimal("1.25"));
+        assert_eq!(decimal("1.50"), decimal("15e-1"));
+    }
+}
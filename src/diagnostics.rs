@@ -0,0 +1,86 @@
+//! Non-fatal diagnostics.
+//!
+//! Procedural macros cannot emit warnings on stable Rust. As a workaround, a warning is
+//! reported by referring to a deprecated item whose deprecation note contains the message.
+//! With the `nightly-diagnostics` feature, warnings are instead emitted as diagnostics of the
+//! compiler, which requires nightly Rust.
+
+use proc_macro2::{Span, TokenStream};
+use syn::Item;
+#[cfg(not(feature = "nightly-diagnostics"))]
+use {proc_macro2::Ident, quote::quote, syn::Stmt};
+
+/// A warning about the decorated item, reported at the given span.
+pub struct Warning {
+    span: Span,
+    message: String,
+}
+
+impl Warning {
+    pub fn new<S: Into<String>>(span: Span, message: S) -> Self {
+        Warning {
+            span,
+            message: message.into(),
+        }
+    }
+
+    /// Emits the warning as a diagnostic of the compiler.
+    #[cfg(feature = "nightly-diagnostics")]
+    fn emit(&self) {
+        proc_macro::Diagnostic::spanned(
+            self.span.unwrap(),
+            proc_macro::Level::Warning,
+            self.message.as_str(),
+        )
+        .emit();
+    }
+
+    /// A block that makes the compiler report the warning.
+    #[cfg(not(feature = "nightly-diagnostics"))]
+    fn to_block(&self) -> TokenStream {
+        let message = &self.message;
+        let usage = Ident::new("numeric_literals_warning", self.span);
+        quote! {
+            {
+                #[deprecated(note = #message)]
+                #[allow(non_camel_case_types)]
+                struct numeric_literals_warning;
+                let _ = #usage;
+            }
+        }
+    }
+}
+
+/// Emits the warnings, without changing the item.
+#[cfg(feature = "nightly-diagnostics")]
+pub fn attach_warnings(_item: &mut Item, warnings: &[Warning]) -> TokenStream {
+    for warning in warnings {
+        warning.emit();
+    }
+    TokenStream::new()
+}
+
+/// Attaches the warnings to the item.
+///
+/// For functions, the warnings are inserted at the start of the body, so that the function
+/// remains valid wherever it appears (e.g. in an impl block). For other items, the warnings
+/// are emitted in an anonymous constant following the item.
+#[cfg(not(feature = "nightly-diagnostics"))]
+pub fn attach_warnings(item: &mut Item, warnings: &[Warning]) -> TokenStream {
+    if warnings.is_empty() {
+        return TokenStream::new();
+    }
+
+    let blocks: Vec<_> = warnings.iter().map(Warning::to_block).collect();
+    if let Item::Fn(item_fn) = item {
+        let stmts = blocks
+            .into_iter()
+            .map(|block| syn::parse2::<Stmt>(block).expect("Warning must be a valid statement"));
+        item_fn.block.stmts.splice(0..0, stmts);
+        TokenStream::new()
+    } else {
+        quote! {
+            const _: () = { #(#blocks)* };
+        }
+    }
+}
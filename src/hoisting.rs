@@ -0,0 +1,100 @@
+//! Hoisting of replaced literals into bindings at the start of function bodies.
+//!
+//! With `hoist = true`, the replacement of each distinct literal in a function body is evaluated
+//! once, in a `let` binding at the start of the body, and every occurrence of the literal is
+//! replaced by a clone of the binding. Cloning keeps the binding usable for types that are not
+//! `Copy`, and is a plain copy for those that are. The bindings are hygienic, so that they cannot
+//! collide with the variables of the function.
+
+use proc_macro2::{Ident, Span};
+use quote::quote_spanned;
+use syn::{Block, Expr, ExprLit, Stmt};
+
+/// Prefix of the names of the bindings.
+const BINDING_PREFIX: &str = "__numeric_literal_";
+
+/// A literal whose replacement is bound at the start of a function body.
+struct HoistedLiteral {
+    /// The source text of the literal
+    text: String,
+    name: Ident,
+    replacement: Expr,
+}
+
+/// The hoisted literals of a function body.
+pub struct Hoisted {
+    literals: Vec<HoistedLiteral>,
+}
+
+impl Hoisted {
+    pub fn new() -> Self {
+        Hoisted {
+            literals: Vec::new(),
+        }
+    }
+
+    /// Returns an expression referring to the binding of the literal.
+    ///
+    /// At the first occurrence of the literal, the binding is created from the replacement
+    /// returned by `instantiate`. Literals are identified by their source text, so that literals
+    /// such as `4.0` and `4.` are bound separately, like `literal_text` would tell them apart.
+    pub fn reference<F>(&mut self, literal: &ExprLit, instantiate: F) -> Result<Expr, syn::Error>
+    where
+        F: FnOnce() -> Result<Expr, syn::Error>,
+    {
+        let text = quote::ToTokens::to_token_stream(&literal.lit).to_string();
+        let name = match self.literals.iter().find(|hoisted| hoisted.text == text) {
+            Some(hoisted) => hoisted.name.clone(),
+            None => {
+                let replacement = instantiate()?;
+                let name = self.binding_name(&text);
+                self.literals.push(HoistedLiteral {
+                    text,
+                    name: name.clone(),
+                    replacement,
+                });
+                name
+            }
+        };
+        // The reference resolves like the binding, but is located at the literal
+        let mut name = name;
+        name.set_span(Span::mixed_site().located_at(literal.lit.span()));
+        let span = Span::call_site().located_at(literal.lit.span());
+        Ok(Expr::Verbatim(quote_spanned! {span=>
+            ::core::clone::Clone::clone(&#name)
+        }))
+    }
+
+    /// Derives a name for the binding of a literal from its source text, e.g. `__numeric_literal_4p0`
+    /// for `4.0`, which differs from the names of all other bindings.
+    fn binding_name(&self, text: &str) -> Ident {
+        let sanitized: String = text
+            .chars()
+            .filter(|c| *c != '_')
+            .map(|c| match c {
+                '.' => 'p',
+                '-' => 'm',
+                c if c.is_ascii_alphanumeric() => c,
+                _ => 'x',
+            })
+            .collect();
+        let mut name = format!("{}{}", BINDING_PREFIX, sanitized);
+        let mut suffix = 1;
+        while self.literals.iter().any(|hoisted| hoisted.name == name) {
+            suffix += 1;
+            name = format!("{}{}_{}", BINDING_PREFIX, sanitized, suffix);
+        }
+        Ident::new(&name, Span::mixed_site())
+    }
+
+    /// Inserts the bindings at the start of the block.
+    pub fn insert_bindings(self, block: &mut Block) {
+        let bindings = self.literals.into_iter().map(|hoisted| {
+            let name = hoisted.name;
+            let replacement = hoisted.replacement;
+            syn::parse_quote!(let #name = #replacement;)
+        });
+        let bindings: Vec<Stmt> = bindings.collect();
+        block.stmts.splice(0..0, bindings);
+    }
+}
@@ -47,9 +47,12 @@
 //! In the example above, if you would additionally need to index into an array with a constant index
 //! such as `array[0]`, the macro will try to convert the index `0` to a float type, which
 //! would clearly fail. Thankfully, in most cases these examples will outright fail to compile
-//! because of type mismatch. One possible resolution to this problem is to use the separate
+//! because of type mismatch, which is reported at the offending literal. One possible resolution
+//! to this problem is to use the separate
 //! macros `replace_float_literals` and `replace_int_literals`, which work in the exact same way,
-//! but only trigger on float or integer literals, respectively. Below is an example from
+//! but only trigger on float or integer literals, respectively. Literals are classified by how they are
+//! written rather than by their value: `20f64` has no decimal point, but its suffix makes it a float literal, while
+//! `20` and `20i32` are integer literals. Below is an example from
 //! Finite Element code that uses float literal replacement to improve readability of numerical
 //! constants in generic code.
 //!
@@ -102,6 +105,71 @@
 //! ```ignore
 //! #[replace_numeric_literals(T::from(literal).unwrap()), visit_macros = false]
 //! ```
+//! Only macros whose body can be parsed as an expression, or as a list of expressions separated by `,` or `;`,
+//! are visited. If the body of a macro cannot be parsed, but contains literals that would otherwise be replaced,
+//! the macro is left untouched, and a warning is emitted at the invocation with `strict = true`. In `matches!` and `assert_matches!`, the pattern is left
+//! untouched, while the other arguments (including the guard) are visited. Similarly, in formatting
+//! macros such as `format!`, `println!` and `write!`, only the arguments following the format string are visited.
+//! With `visit_format_args = false`, the arguments of formatting macros are left untouched as well.
+//! With `visit_unsafe = false`, `unsafe` blocks are left untouched, including the macros in them, which is
+//! useful when their literals are offsets or flags rather than numbers.
+//! Inline `const { ... }` blocks are left untouched by default, since they are typically array lengths or indices
+//! of concrete types, in which a replacement such as `T::from(literal)` cannot be evaluated. They are visited
+//! with `visit_inline_const = true`.
+//! Literals in `where` clauses, such as the array length in `where S: Shape<Dims = [usize; 2]>`, are visited
+//! like any other literals. With `visit_where_literals = false`, `where` clauses are left untouched.
+//! Const generic arguments of types, such as the dimensions in `SMatrix::<T, 4, 4>::zeros()`, are visited as
+//! well and enclosed in braces when replaced, unless `visit_generic_args = false`. Const generic arguments of
+//! the called function or method itself, as in `from_fn::<_, 4, _>`, are always left untouched.
+//! With `skip_shift_rhs = true`, the right-hand sides of the shifts `<<`, `>>`, `<<=` and `>>=` are left untouched,
+//! so that shift amounts such as the `3` in `x >> 3` remain machine integers.
+//! Similarly, with `skip_ranges = true`, literals that are endpoints of ranges, as in `0..n` and `data[2..8]`, are left
+//! untouched. Literals nested deeper in an endpoint, as in `0..n - 1`, are still replaced.
+//! To instead be forced to decide at each index, `deny_index_replacement = true` makes it a compile error to replace a
+//! literal in the index of an indexing expression, as in `array[0]`, at any depth.
+//! Conversely, with `promote_ints = true`, `replace_float_literals` also replaces unsuffixed integer literals, which are
+//! first rewritten as the equivalent float literal, so that `2` is replaced like `2.0`. Suffixed integer literals such
+//! as `3usize`, as well as array lengths and const generic arguments, are left untouched.
+//! With `only_in_calls = [Vector2::new, splat]`, only literals in the arguments of calls to the listed functions
+//! and methods are replaced, at any depth. A listed path matches calls whose path ends with it, so `new` matches
+//! `Vector2::new` as well, and methods are matched by their name alone.
+//! Conversely, with `skip_in_calls = [with_capacity, reserve, take, skip, chunks]`, literals in the arguments of
+//! calls to the listed functions and methods are left untouched, at any depth, which keeps counts and capacities
+//! as `usize`.
+//! Similarly, with `only_in_bindings = [alphas, betas, phi, phi_grad]`, only literals in the initializers of `let`
+//! statements whose pattern binds one of the listed names are replaced, which leaves the indexing around literal
+//! tables untouched. If several of these parameters are given, a literal is only replaced if it satisfies all of them.
+//!
+//! With the `macro_fallback = tokens` parameter, such macro bodies are instead searched for numeric literal
+//! tokens, which are replaced individually. Literals in attributes (e.g. `#[cfg(...)]`) are left untouched.
+//! This is best-effort: the fallback knows nothing about the grammar of the macro, so it also replaces
+//! literals in positions where an expression is not allowed, which may lead to confusing errors.
+//! The body of `serde_json::json!` is always handled like this, so that the values in JSON objects are replaced.
+//! With `macro_fallback = error`, such macro bodies are instead a compile error, and the default is `macro_fallback = skip`.
+//!
+//! The bodies of inline assembly macros (`asm!`, `global_asm!` and `naked_asm!`) are never visited. Neither are the arguments of `offset_of!`, since its field names may be numbers such as `pair.1`.
+//!
+//! If the attribute is placed on a `macro_rules!` definition, the literals in the transcribers of its rules are
+//! replaced, such as the `2.0` in `($x:expr) => { $x * 2.0 };`, while the matchers and tuple fields such as `$x.0`
+//! are left untouched. Definitions of `macro_rules!` inside an item with the attribute are left untouched.
+//!
+//! For other macros, such as the macros of domain-specific crates, the parser of the body can be chosen by
+//! the name of the macro with the `macro_parsers` parameter, which takes precedence over the built-in handling:
+//! ```rust
+//! # use numeric_literals::replace_float_literals;
+//! macro_rules! entries {
+//!     ($($key:ident => $value:expr),*) => { vec![$((stringify!($key), $value)),*] };
+//! }
+//!
+//! #[replace_float_literals(literal * 2.0, macro_parsers = [entries = tokens])]
+//! fn doubled() -> Vec<(&'static str, f64)> {
+//!     entries!(a => 1.5, b => 2.5)
+//! }
+//! # assert_eq!(doubled(), vec![("a", 3.0), ("b", 5.0)]);
+//! ```
+//! The available parsers are `expressions` (like `vec!`), `format` (a format string followed by arguments, like
+//! `format!`), `pattern` (an expression followed by a pattern, like `matches!`), `tokens` (like
+//! `macro_fallback = tokens`) and `skip`, which leaves the body untouched.
 //!
 //! Literals with suffixes
 //! ----------------------
@@ -118,40 +186,935 @@
 //!     (1.0_f64 + 5f32.sqrt()) / 2.0
 //! }
 //! ```
+//!
+//! Fixed-point literals
+//! --------------------
+//! For fixed-point and decimal types, it is often more appropriate to construct values from an integer
+//! number of "minor units" than from a (possibly inexact) float. The `literal_scaled` placeholder is replaced
+//! by the literal multiplied by `10^scale` as an integer literal, where `scale` is given as a parameter.
+//! The scaled value is computed exactly from the digits of the literal, so `1.25` with `scale = 4` becomes `12500`:
+//!
+//! ```rust
+//! # use numeric_literals::replace_float_literals;
+//! struct Money(i64);
+//!
+//! impl Money {
+//!     fn from_minor_units(units: i64) -> Self {
+//!         Money(units)
+//!     }
+//! }
+//!
+//! #[replace_float_literals(Money::from_minor_units(literal_scaled), scale = 4)]
+//! fn price() -> Money {
+//!     1.25
+//! }
+//! ```
+//! It is a compile error if a literal has more fractional digits than the scale allows.
+//!
+//! Arbitrary-precision types
+//! -------------------------
+//! Replacements need not be `Copy`. With `rug::Float`, every replaced literal constructs a new `Float` with the
+//! precision given in the replacement, and arithmetic on replaced literals, such as `0.1 + 0.2`, works as is,
+//! since both operands are owned values. Arithmetic moves its operands, so variables that are used more than once
+//! are borrowed with `&` or cloned. To sum the exact decimal values of the literals rather than the nearest `f64`
+//! values, the source text of the literals is parsed instead:
+//!
+//! ```rust,ignore
+//! use rug::Float;
+//!
+//! const PRECISION: u32 = 128;
+//!
+//! #[replace_float_literals(Float::with_val(PRECISION, literal))]
+//! fn sqrt(x: &Float) -> Float {
+//!     let mut y = (x.clone() + 1.0) / 2.0;
+//!     for _ in 0..8 {
+//!         y = (x.clone() / &y + &y) / 2.0;
+//!     }
+//!     y
+//! }
+//!
+//! #[replace_float_literals(Float::with_val(PRECISION, Float::parse(literal_text).unwrap()))]
+//! fn exact_sum() -> Float {
+//!     0.1 + 0.2
+//! }
+//! ```
+//!
+//! Const functions
+//! ---------------
+//! Literals in the bodies of `const fn`s and in the values of constants, including associated constants in
+//! `impl` blocks and traits, are replaced like any other literals. However, the replacement expression must
+//! then be const-evaluable. Since the macros cannot know whether a function called in the replacement
+//! expression is a `const fn`, they warn about `const fn`s and constants in which a replacement expression
+//! containing calls is used. If the replacement is const-evaluable, the warning can be silenced with the
+//! `const_safe` parameter:
+//!
+//! ```rust
+//! # use numeric_literals::replace_int_literals;
+//! #[replace_int_literals(i64::pow(literal, 2), const_safe = true)]
+//! const fn nine() -> i64 {
+//!     3
+//! }
+//! ```
+//!
+//! Traits
+//! ------
+//! On a trait, the default bodies of its methods and the default values of its constants are visited. The
+//! signatures, bounds and generics of the trait and its items are part of its interface and are left untouched,
+//! so array lengths such as the `2` in `[Self; 2]` remain `usize`:
+//!
+//! ```rust
+//! # use num::FromPrimitive;
+//! # use numeric_literals::replace_float_literals;
+//!
+//! #[replace_float_literals(Self::from_f64(literal).unwrap())]
+//! trait Kernel: FromPrimitive {
+//!     fn weights(&self) -> [Self; 2] {
+//!         [0.25, 0.75]
+//!     }
+//! }
+//! ```
+//!
+//! Foreign functions and statics in an `extern` block have no bodies, so the macros leave such blocks unchanged.
+//! Literals that would be replaced can only appear in their types, such as the `4` in `static TABLE: [u8; 4];`,
+//! and are reported as errors.
+//!
+//! Modules
+//! -------
+//! The macros can be applied to any item, including inline modules, in which case all items in the
+//! module are visited. Keep in mind that the replacement expression is inserted verbatim in place of each
+//! literal, so any names it refers to must be in scope everywhere in the module. For example, with
+//! `T::from(literal)`, every literal in the module must appear in an item with a type parameter `T`:
+//!
+//! ```rust
+//! # use numeric_literals::replace_numeric_literals;
+//! #[replace_numeric_literals(T::from(literal))]
+//! mod generic {
+//!     pub fn three<T: From<i8>>() -> T {
+//!         3
+//!     }
+//!
+//!     pub fn five<T: From<i8> + std::ops::Add<Output = T>>() -> T {
+//!         2 + 3
+//!     }
+//!
+//!     // This would not compile, since `T` is not in scope:
+//!     // pub fn seven() -> i32 { 7 }
+//! }
+//! ```
+//!
+//! The attribute of the module serves as a default: items in the module with their own attribute of this crate,
+//! including methods in impl blocks, are left to their own attribute. This makes it possible to, for example,
+//! override the replacement expression for the non-generic items of an otherwise generic module:
+//!
+//! ```rust
+//! # use numeric_literals::replace_numeric_literals;
+//! #[replace_numeric_literals(T::from(literal))]
+//! mod generic {
+//!     use numeric_literals::replace_numeric_literals;
+//!
+//!     pub fn three<T: From<i8>>() -> T {
+//!         3
+//!     }
+//!
+//!     #[replace_numeric_literals(literal as i64)]
+//!     pub fn seven() -> i64 {
+//!         7
+//!     }
+//! }
+//! ```
+//!
+//! The same holds for functions nested in a decorated function. Attributes are recognized by name, also when written
+//! as a path such as `numeric_literals::replace_float_literals`, or when renamed with `use ... as ...` inside the
+//! decorated item. Renamed imports outside of the decorated item cannot be detected.
+//!
+//! To apply an attribute to a whole file, such as a module in its own file or the root of a crate, the items can
+//! be wrapped in `apply_to_module!`, with the attributes of this crate written as inner attributes at its start.
+//! The items are visited as if they were in a module with these attributes, so the innermost attribute wins as
+//! described above:
+//!
+//! ```rust
+//! # use num::Float;
+//! numeric_literals::apply_to_module! {
+//!     #![replace_float_literals(T::from(literal).unwrap())]
+//!
+//!     pub fn half<T: Float>() -> T {
+//!         0.5
+//!     }
+//! }
+//! # assert_eq!(half::<f32>(), 0.5);
+//! ```
+//!
+//! On nightly Rust, the attributes can instead be written as inner attributes of an inline module, such as
+//! `mod generic { #![numeric_literals::replace_float_literals(...)] ... }`, with `#![feature(custom_inner_attributes)]`.
+//! With `#![feature(proc_macro_hygiene)]` in addition, they can also be written at the top of a module in its own file.
+//!
+//! Constant folding
+//! ----------------
+//! An expression such as `1.0 / 3.0` is replaced as two conversions and a generic division, which may be slow for
+//! arbitrary-precision types, and may round differently than the same expression in `f64`. With `fold_constants = true`,
+//! every maximal subexpression that consists solely of unsuffixed float literals, parentheses, unary minus and the
+//! operators `+`, `-`, `*` and `/` is first evaluated in `f64`, and the result is replaced as a single literal:
+//!
+//! ```rust
+//! # use num::Float;
+//! # use numeric_literals::replace_float_literals;
+//! #[replace_float_literals(T::from(literal).unwrap(), fold_constants = true)]
+//! fn third<T: Float>(x: T) -> T {
+//!     // Becomes `x * (T::from(0.3333333333333333).unwrap())`
+//!     x * (1.0 / 3.0)
+//! }
+//! ```
+//!
+//! Folding does not cross other operands, so `x * 2.0 * 0.5` is left as it is, since it is evaluated as `(x * 2.0) * 0.5`.
+//! A subexpression that evaluates to infinity or NaN, such as `1.0 / 0.0`, is a compile error.
+//!
+//! Exactness checks
+//! ----------------
+//! Float literals such as `0.1` cannot be represented exactly in binary floating point. When the replacement
+//! converts literals to `f32`, e.g. `T::from_f32(literal as f32).unwrap()`, the loss of precision may be
+//! unexpected. The `deny_inexact_f32` parameter makes it a compile error to use a float literal which is not
+//! exactly representable as `f32`, and reports the nearest representable value. For gradual adoption,
+//! `warn_inexact_f32` reports the same problem as a warning instead.
+//!
+//! Similarly, `warn_inexact` warns about float literals whose exact decimal value differs from the value
+//! of the literal as `f64` (or `f32` for literals with an `f32` suffix). This is useful for e.g. financial
+//! code, where the replacement should instead use an exact placeholder such as `literal_scaled`.
+//!
+//! ```rust
+//! # use numeric_literals::replace_float_literals;
+//! #[replace_float_literals(literal as f32, deny_inexact_f32 = true)]
+//! fn quarter() -> f32 {
+//!     // Using 0.1 here would fail to compile
+//!     0.25
+//! }
+//! ```
+//!
+//! Additional placeholders
+//! -----------------------
+//! Besides `literal`, the replacement expression may contain the following placeholders, which are
+//! replaced with information about each literal:
+//!
+//!  - `literal_scaled`: the literal multiplied by `10^scale` (see [Fixed-point literals](#fixed-point-literals)).
+//!  - `literal_kind`: the string literal `"int"` or `"float"`, depending on the kind of the literal.
+//!    This allows a single `replace_numeric_literals` replacement to dispatch on the kind of literal.
+//!  - `literal_index`: the 0-based index of the literal among the replaced literals of the decorated item,
+//!    in the order in which they appear. This can be used e.g. to generate distinct identifiers.
+//!  - `literal_text`: the source text of the literal as a string literal, e.g. `"1.618"`. This is useful for
+//!    arbitrary-precision types that are constructed by parsing a string.
+//!  - `literal_float`: the literal as a float literal, e.g. `3.0` for `3`, while float literals are kept as
+//!    they are. This allows a single replacement such as `T::from_f64(literal_float)` for both kinds of literals.
+//!    Integer literals that are not exactly representable as `f64`, such as `9007199254740993`, are rejected.
+//!  - `literal_file`, `literal_line` and `literal_column`: the location of the literal in the source, like
+//!    `file!()`, `line!()` and `column!()` at the literal. This is useful for messages of failed conversions.
+//!    Procedural macros only know the locations of tokens since Rust 1.88. On earlier versions, `literal_file` is the
+//!    name of the decorated item, `literal_line` is the 1-based index of the literal within the item and
+//!    `literal_column` is `0`.
+//!
+//! ```rust
+//! # use numeric_literals::replace_numeric_literals;
+//! #[replace_numeric_literals(if literal_kind == "int" { literal as f64 * 10.0 } else { literal as f64 })]
+//! fn values() -> (f64, f64) {
+//!     (3, 2.5)
+//! }
+//! ```
+//!
+//! Range checks
+//! ------------
+//! A replacement such as `T::from(literal)` with `T: From<i8>` only compiles for literals that fit in `i8`, and
+//! conversions like `T::from(literal).unwrap()` fail at runtime for literals that are out of range. The `fits`
+//! parameter verifies at compile time that every integer literal is representable in the given primitive integer
+//! type, while `min` and `max` check against explicit bounds. Negated literals such as `-5` are checked with their
+//! sign. Note that `isize` and `usize` are assumed to be 64 bits wide.
+//!
+//! ```rust
+//! # use numeric_literals::replace_int_literals;
+//! #[replace_int_literals(T::from(literal), fits = i8)]
+//! fn offsets<T: From<i8> + std::ops::Neg<Output = T>>() -> (T, T) {
+//!     // Using 200 here would fail to compile
+//!     (-100, 100)
+//! }
+//! ```
+//!
+//! Suffixed literals
+//! -----------------
+//! By default, suffixed literals such as `1.0f64` are replaced like any other literal, even though the suffix
+//! no longer describes the type of the result. With `ignore_suffixed = true`, suffixed literals are left untouched,
+//! which makes it possible to opt individual literals out of the replacement. With `deny_suffixed = true`, a suffixed
+//! literal is a compile error instead. The two parameters cannot be combined.
+//!
+//! ```rust
+//! # use num::Float;
+//! # use numeric_literals::replace_float_literals;
+//! #[replace_float_literals(T::from(literal).unwrap(), ignore_suffixed = true)]
+//! fn scale<T: Float>(x: T) -> (T, f64) {
+//!     (x * 2.0, 2.0f64)
+//! }
+//! ```
+//!
+//! Routing by value
+//! ----------------
+//! Instead of a single replacement expression, a `match_value { ... }` block selects the replacement by the value
+//! of each literal. The arms are evaluated when the macro is expanded, in order, and the first arm whose pattern
+//! matches the exact value of the literal is used. Patterns may be `_`, numeric literals, ranges such as `0.0..1.0`
+//! or `1..=10`, and alternatives of these separated by `|`. A literal that matches no arm is a compile error.
+//! Note that the negation in `-0.5` is not part of the literal, so it is routed by the value `0.5`.
+//! The same applies to a `match` on the literal itself, such as
+//! `match literal { 0 => T::zero(), 1 => T::one(), _ => T::from(literal).unwrap() }`, which is resolved when the
+//! macro is expanded instead of being emitted as a `match`. This requires every arm to be `_`, a literal or a range
+//! without a guard. Other matches, such as `match literal { x => x as f32 }`, are emitted unchanged.
+//!
+//! ```rust
+//! # use numeric_literals::replace_float_literals;
+//! #[replace_float_literals(match_value {
+//!     0.0..1.0 => literal as f32 as f64,
+//!     _ => literal,
+//! })]
+//! fn weights() -> (f64, f64) {
+//!     (0.5, 2.5)
+//! }
+//! ```
+//!
+//! Zero and non-zero literals
+//! --------------------------
+//! Zero often has a dedicated constructor, such as `Zero::zero()` from `num`. The `replace_zero_literals` and
+//! `replace_nonzero_literals` macros behave like `replace_numeric_literals`, but only replace the literals whose
+//! value is zero (e.g. `0`, `0.0`, `0u32` or `0e5`) or not zero, respectively. Combined, they replace every numeric
+//! literal exactly once.
+//!
+//! ```rust
+//! # use num::Float;
+//! # use numeric_literals::{replace_nonzero_literals, replace_zero_literals};
+//! #[replace_zero_literals(T::zero())]
+//! #[replace_nonzero_literals(T::from(literal).unwrap())]
+//! fn axis<T: Float>() -> (T, T, T) {
+//!     (0.0, 1.0, 0.0)
+//! }
+//! ```
+//!
+//! Similarly, `replace_one_literals` only replaces literals whose value is one (e.g. `1`, `1.0` or `10e-1`), such
+//! as with `One::one()`. Since these literals are also not zero, `replace_one_literals` must be placed above
+//! `replace_nonzero_literals`, so that it is expanded first:
+//!
+//! ```rust
+//! # use num::Float;
+//! # use numeric_literals::{replace_nonzero_literals, replace_one_literals, replace_zero_literals};
+//! #[replace_zero_literals(T::zero())]
+//! #[replace_one_literals(T::one())]
+//! #[replace_nonzero_literals(T::from(literal).unwrap())]
+//! fn weights<T: Float>() -> (T, T, T) {
+//!     (0.0, 1.0, 2.5)
+//! }
+//! ```
+//!
+//! Unsigned literals
+//! -----------------
+//! In low-level code, literals with an unsigned suffix such as `0xFFu8` or `65535u32` often stand for bit patterns
+//! or sizes rather than numbers. The `replace_uint_literals` macro behaves like `replace_int_literals`, but only
+//! replaces integer literals with the suffix of an unsigned type (`u8`, `u16`, `u32`, `u64`, `u128` or `usize`).
+//! Unsuffixed literals are left untouched, since their type is only known to the compiler.
+//!
+//! Newer syntax
+//! ------------
+//! Items are parsed with version 1 of `syn`, which keeps some syntax that is newer than it as unparsed tokens.
+//! Of such syntax, `let ... else` statements and inline `const` blocks are recognized, and visited like any other
+//! code. Other such syntax, such as the raw borrow `&raw const place`, is kept as written, except that literal
+//! tokens in it are replaced individually, as with `macro_fallback = tokens`. Chains of `let` in the conditions of
+//! `if` and `while` are parsed by `syn`, and are visited like any other expressions.
+//!
+//! Nightly features
+//! ----------------
+//! Literals in syntax that is only available on nightly Rust, such as `try` blocks and the values of `yield` in
+//! coroutines, are replaced like any other literals. The tests for such syntax are enabled with the
+//! `numeric_literals_nightly` cfg, and can be run with
+//! `RUSTFLAGS="--cfg numeric_literals_nightly" cargo +nightly test --test nightly`.
+//!
+//! Stacking attributes
+//! -------------------
+//! Several attributes of this crate can be stacked on the same item. They are applied together when the topmost
+//! attribute is expanded, in order from top to bottom, and each literal is replaced by the first attribute that
+//! applies to it. Literals in the replacements of one attribute are never replaced by the other attributes, so
+//! the order only matters for literals that more than one of the attributes applies to. For example, the integer
+//! literal produced by `literal_scaled` is left alone by `replace_int_literals`:
+//!
+//! ```rust
+//! # use numeric_literals::{replace_float_literals, replace_int_literals};
+//! #[replace_float_literals(literal_scaled as i64, scale = 2)]
+//! #[replace_int_literals(literal as i64 * 100)]
+//! fn cents() -> (i64, i64) {
+//!     (2.5, 3)
+//! }
+//!
+//! assert_eq!(cents(), (250, 300));
+//! ```
+//!
+//! Expected number of replacements
+//! -------------------------------
+//! The `expect_replacements` parameter makes it a compile error if the attribute does not replace exactly the given
+//! number of literals, including those in macro invocations. This guards against literals that are silently skipped,
+//! such as in a macro body that cannot be parsed, and against replacing more literals than intended after an edit.
+//!
+//! ```rust
+//! # use numeric_literals::replace_float_literals;
+//! #[replace_float_literals(literal as f32, expect_replacements = 3)]
+//! fn weights() -> (f32, [f32; 2]) {
+//!     (0.5, [0.25, 0.25])
+//! }
+//! ```
+//!
+//! Tracing replacements
+//! --------------------
+//! With `trace = true`, the attribute emits a warning for each literal it replaces, such as
+//! ``replaced float literal `4.0` ``, which the compiler reports at the location of the literal. This makes it
+//! possible to review exactly which literals an attribute on a large item, such as an impl block, has replaced.
+//! The parameter is meant to be removed again once the review is done.
+//!
+//! With `dry_run = true`, the attribute instead emits a warning for each literal it would replace, such as
+//! ``would replace float literal `1.618` with `T :: from(1.618).unwrap()` ``, and leaves the item unchanged. This
+//! allows auditing an attribute before it is applied to a large existing item.
+//!
+//! With `expansion_markers = true`, each replacement is instead wrapped in a block that binds it to a variable named
+//! `__numeric_literal_replaced__`, as in `{ let __numeric_literal_replaced__ = T::from(4.0).unwrap(); __numeric_literal_replaced__ }`.
+//! This makes the replacements easy to find in the output of `cargo expand`, without changing the behavior of the
+//! code. The binding is hygienic, so it cannot shadow variables of the item. Since the replacement is moved into the
+//! binding, a replacement that borrows a temporary, such as `&T::from(literal)`, does not compile with this parameter.
+//!
+//! Variables in replacements
+//! -------------------------
+//! The replacement expression is inserted in place of each literal, so a variable in it, such as `scale` in
+//! `literal * scale`, refers to whichever variable of that name is in scope at the literal. Since this is easily
+//! unintended, the attribute emits a warning for such names. Names that are intended to refer to the scope of the
+//! literals are listed with `captures = [scale]`, which silences the warning. Names that start with an uppercase
+//! letter, such as the constant `N`, are not considered variables.
+//!
+//! Some other mistakes in replacement expressions are reported at the attribute, rather than at every literal.
+//! Placeholders are only replaced in expressions, so a placeholder used as a type, as in `Vec::<literal>::new()`, or in
+//! the body of a macro, as in `format!("{}", literal)`, is an error. A replacement that discards the literal, such as
+//! `{ literal; }`, is reported as a warning. With `strict = true`, a replacement without any placeholder, which
+//! replaces every literal with the same value, is an error as well, except for `replace_zero_literals` and
+//! `replace_one_literals`:
+//! ```rust,compile_fail
+//! # use numeric_literals::replace_float_literals;
+//! #[replace_float_literals(1.5, strict = true)]
+//! fn constant() -> f64 {
+//!     0.5 + 2.0
+//! }
+//! ```
+//!
+//! Items without expressions
+//! -------------------------
+//! An attribute on an item that contains no expressions, such as a unit struct, a type alias, an `extern` block or an
+//! empty trait, has no effect. Since this usually means that the attribute was placed on the wrong item, it emits a
+//! warning in this case. With `strict = true`, it is a compile error instead.
+//!
+//! Hoisting literals
+//! -----------------
+//! A literal that occurs many times in a function is converted at every occurrence, which may be costly for
+//! arbitrary-precision types, or for conversions that the optimizer cannot remove. With `hoist = true`, the
+//! replacement of each distinct literal is instead evaluated once, in a binding at the start of the function body,
+//! and each occurrence of the literal is replaced with a clone of the binding:
+//! ```rust
+//! # use numeric_literals::replace_float_literals;
+//! #[replace_float_literals(T::from(literal).unwrap(), hoist = true)]
+//! fn quadratic<T: num::Float>(x: T) -> T {
+//!     4.0 * x * x - 4.0 * x + 4.0
+//! }
+//! # assert_eq!(quadratic(1.0), 4.0);
+//! ```
+//! Here, `T::from(4.0).unwrap()` is only evaluated once per call. Since the bindings are evaluated before the rest
+//! of the function body, the replacement may refer to the parameters of the function, but not to its local
+//! variables. Literals in closures, async blocks, array lengths, types and nested items cannot refer to the
+//! bindings, so they are replaced in place as usual, and so are the literals in `const fn`s. The `literal_index`,
+//! `literal_line` and `literal_column` placeholders cannot be used with `hoist = true`, since they differ between
+//! occurrences of the same literal.
+//!
+//! Presets
+//! -------
+//! Most replacement expressions are one of a few common conversions. Instead of a replacement expression, the
+//! `preset` parameter selects one of them by name, with the target type given by the `generic` parameter (`T` by
+//! default):
+//!
+//! - `preset = "from_primitive"` replaces float literals with `T::from_f64(literal).expect("literal must fit in T")`,
+//!   and integer literals with `T::from_u64(literal).expect("literal must fit in T")`, using `FromPrimitive` from
+//!   `num-traits`.
+//! - `preset = "from"` replaces literals with `T::from(literal)`.
+//! - `preset = "as"` replaces literals with `literal as T`.
+//!
+//! ```rust
+//! # use numeric_literals::replace_numeric_literals;
+//! #[replace_numeric_literals(preset = "from_primitive", generic = S)]
+//! fn half_plus_one<S: num::FromPrimitive + std::ops::Add<Output = S>>() -> S {
+//!     0.5 + 1
+//! }
+//! # assert_eq!(half_plus_one::<f64>(), 1.5);
+//! ```
+//! Presets can be combined with the other parameters, but not with a replacement expression.
+//!
+//! Suffixes
+//! --------
+//! If the replacement is just the name of a primitive numeric type, such as `f32` or `u64`, the literals are not
+//! replaced with an expression. Instead, the type is attached to each literal as its suffix, overwriting any suffix it
+//! already has, so that `1.5` becomes `1.5f32`. The literals remain literals, so this works in `const fn`s, array
+//! lengths and patterns, where a replacement expression cannot be used:
+//!
+//! ```rust
+//! # use numeric_literals::replace_float_literals;
+//! #[replace_float_literals(f32)]
+//! const fn unit_square() -> [[f32; 2]; 4] {
+//!     [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]]
+//! }
+//! # assert_eq!(unit_square()[2], [1.0f32, 1.0]);
+//! ```
+//! Integer literals given an integer suffix must fit in the type, as with the `fits` parameter. Integer literals
+//! written in decimal may be given a float suffix, while float literals cannot be given an integer suffix.
+//!
+//! Function paths
+//! --------------
+//! Many replacements just pass the literal to a conversion function. If the replacement is a path without a
+//! placeholder, it is taken to be such a function and called with the literal, so that `nalgebra::convert` is
+//! short for `nalgebra::convert(literal)` and `T::from_f64` for `T::from_f64(literal)`:
+//!
+//! ```rust
+//! # use numeric_literals::replace_float_literals;
+//! #[replace_float_literals(nalgebra::convert)]
+//! fn circumference<T: nalgebra::RealField + Copy>(radius: T) -> T {
+//!     T::pi() * radius * 2.0
+//! }
+//! # assert_eq!(circumference(0.5), std::f64::consts::PI);
+//! ```
+//! Only paths ending in a lowercase name are taken to be functions, so that constants such as `N` are inserted as
+//! they are. Paths to primitive types such as `f32` are instead attached to the literals as their suffix.
+//!
+//! Arithmetic context
+//! ------------------
+//! Rather than skipping positions such as indices, lengths and ranges one by one, the `context = arithmetic` parameter
+//! only replaces literals where they plausibly take part in generic math:
+//!
+//! - Operands of `+`, `-`, `*`, `/` and `%`, of the corresponding compound assignments such as `+=`, and of the
+//!   comparisons `==`, `!=`, `<`, `<=`, `>` and `>=`, unless the other operand is a literal as well.
+//! - Arguments of method calls whose receiver is not a literal, as in `x.max(0.0)`.
+//!
+//! Parentheses and negation do not change whether a literal is an operand, so the literals in `x * (2.0)` and
+//! `x * -2.0` are replaced. All other literals are left untouched, including indices, lengths, range endpoints, shift
+//! amounts, arguments of function calls and the elements of `vec![...]`.
+//!
+//! ```rust
+//! # use numeric_literals::replace_numeric_literals;
+//! # use num::Float;
+//! #[replace_numeric_literals(T::from(literal).unwrap(), context = arithmetic)]
+//! fn weighted<T: Float>(values: &[T]) -> T {
+//!     values[0] * 0.25 + values[1].max(0.5)
+//! }
+//! # assert_eq!(weighted(&[2.0, 0.0]), 1.0);
+//! ```
+//!
+//! Debug output
+//! ------------
+//! For tools such as editor extensions, the `debug-output` feature reports every replaced literal as a line of
+//! JSON on the standard error of the compiler, if the environment variable `NUMERIC_LITERALS_DEBUG` is set to `1`:
+//!
+//! ```text
+//! {"kind":"float","literal":"3.14","replacement":"T :: from_f64 (3.14) . unwrap ()","span":{"col":8,"end_col":12,"end_line":42,"line":42}}
+//! ```
+//!
+//! Lines and columns are counted from 1, and the end of the span is exclusive. Without the feature, which also
+//! pulls in `serde_json`, the variable has no effect.
+//!
+//! Warnings
+//! --------
+//! Procedural macros cannot emit warnings on stable Rust, so the warnings of this crate are reported as uses of a
+//! deprecated item, such as ``use of deprecated unit struct `numeric_literals_warning`: replaced float literal `4.0` ``.
+//! Like other deprecation warnings, they can be silenced with `#[allow(deprecated)]`. With the `nightly-diagnostics`
+//! feature, which requires nightly Rust, they are instead emitted as regular warnings of the compiler, without the
+//! deprecation note and without adding any code to the item. Such warnings cannot be silenced with `allow` attributes.
+
+#![cfg_attr(feature = "nightly-diagnostics", feature(proc_macro_diagnostic))]
 
 extern crate proc_macro;
 use proc_macro::TokenStream;
+use proc_macro2::{Delimiter, Group, Ident, Spacing, Span, TokenTree};
+use std::convert::TryFrom;
+
+#[cfg(feature = "debug-output")]
+mod debug_output;
+mod decimal;
+mod diagnostics;
+mod hoisting;
+mod presets;
+mod routing;
+mod template;
+mod validation;
+mod verbatim;
 
-use syn::parse::Parser;
-use syn::punctuated::Punctuated;
+use decimal::{Decimal, FloatFormat, ScaleError};
+use diagnostics::{attach_warnings, Warning};
+use hoisting::Hoisted;
+use presets::Preset;
+use routing::Replacement;
+use template::{Placeholder, Template};
+use validation::validate_replacement;
+use verbatim::VerbatimExpr;
+
+use syn::parse::{Parse, ParseStream, Parser};
+use syn::spanned::Spanned;
 use syn::visit::Visit;
-use syn::visit_mut::{visit_expr_mut, VisitMut};
+use syn::visit_mut::{
+    visit_const_param_mut, visit_expr_assign_op_mut, visit_expr_async_mut, visit_expr_binary_mut,
+    visit_expr_closure_mut, visit_expr_mut, visit_expr_range_mut, visit_expr_unsafe_mut,
+    visit_generic_argument_mut, visit_impl_item_method_mut, visit_item_fn_mut,
+    visit_item_foreign_mod_mut, visit_item_mut, visit_local_mut, visit_type_mut,
+    visit_where_clause_mut, VisitMut,
+};
 use syn::{
-    parse_macro_input, Expr, ExprAssign, ExprLit, ExprPath, Item, Lit, LitBool, Macro, Token,
+    parse_macro_input, parse_quote, Attribute, BinOp, Block, ConstParam, Expr, ExprAssign,
+    ExprAssignOp, ExprAsync, ExprBinary, ExprBlock, ExprCall, ExprClosure, ExprGroup, ExprIndex,
+    ExprLit, ExprMethodCall, ExprParen, ExprPath, ExprRange, ExprRepeat, ExprUnary, ExprUnsafe,
+    GenericArgument, GenericMethodArgument, ImplItemConst, ImplItemMethod, Item, ItemConst, ItemFn,
+    ItemForeignMod, ItemTrait, Lit, LitBool, LitFloat, LitInt, LitStr, Local, Macro,
+    MethodTurbofish, Pat, PatIdent, PathArguments, Signature, Stmt, Token, TraitItemConst,
+    TraitItemMethod, TraitItemType, Type, UnOp, UseRename, WhereClause,
 };
 
 use quote::{quote, ToTokens};
 
-/// Visit an expression and replaces any numeric literal
-/// with the replacement expression, in which a placeholder identifier
-/// is replaced with the numeric literal.
-struct NumericLiteralVisitor<'a> {
-    pub parameters: MacroParameters,
-    pub placeholder: &'a str,
-    pub float_replacement: &'a Expr,
-    pub int_replacement: &'a Expr,
+/// Visits an item and replaces the numeric literals selected by the attribute with the
+/// replacement expression, in which a placeholder identifier is replaced with the numeric literal.
+///
+/// The same visitor serves all attribute macros of this crate, which only differ in the kinds
+/// and values of the literals that they replace.
+struct LiteralVisitor<'a> {
+    pub parameters: &'a MacroParameters,
+    /// Names under which attribute macros of this crate are imported in the decorated item
+    pub attribute_aliases: &'a [String],
+    /// Replacement of float literals, if they are replaced
+    pub float_replacement: Option<&'a Replacement<Template>>,
+    /// Replacement of integer literals, if they are replaced
+    pub int_replacement: Option<&'a Replacement<Template>>,
+    /// Primitive type whose suffix is attached to float literals instead of replacing them
+    pub float_suffix: Option<PrimitiveSuffix>,
+    /// Primitive type whose suffix is attached to integer literals instead of replacing them
+    pub int_suffix: Option<PrimitiveSuffix>,
+    /// Values of the literals that are replaced
+    pub values: LiteralValues,
+    pub errors: Vec<syn::Error>,
+    pub warnings: Vec<Warning>,
+    /// Index of the next literal to be replaced, in visitation order
+    pub current_index: usize,
+    /// Name of the decorated item, for the location placeholders without span locations
+    pub item_name: &'a str,
+    /// Literals hoisted to the start of the enclosing function body with `hoist = true`, unless
+    /// the current position cannot refer to bindings there (e.g. in a closure)
+    pub hoisted: Option<Hoisted>,
+    /// Whether the expression visited next is an arithmetic operand, with `context = arithmetic`
+    pub operand: bool,
+    /// Whether the current expression is an argument of a call listed in `only_in_calls`
+    pub in_listed_call: bool,
+    /// Whether the current expression is an argument of a call listed in `skip_in_calls`
+    pub in_skipped_call: bool,
+    /// Whether the current expression is in the initializer of a binding listed in `only_in_bindings`
+    pub in_listed_binding: bool,
+    /// Whether the current expression is an array length or a const generic argument
+    pub in_length: bool,
+    /// Whether the current expression is in the declaration of a foreign item in an `extern` block
+    pub in_foreign_item: bool,
+    /// Whether the current expression is in the index of an indexing expression, as in `a[0]`
+    pub in_index: bool,
+}
+
+/// Selects numeric literals by their value, or by their suffix.
+#[derive(Copy, Clone)]
+enum LiteralValues {
+    All,
+    Zero,
+    One,
+    NonZero,
+    /// Integer literals with the suffix of an unsigned integer type, e.g. `0xFFu8`
+    Unsigned,
+}
+
+impl LiteralValues {
+    fn includes(self, lit: &Lit) -> bool {
+        // The value is only parsed for the attributes that select literals by it
+        let is_zero = || matches!(Decimal::from_lit(lit), Some(decimal) if decimal.is_zero());
+        match self {
+            LiteralValues::All => true,
+            LiteralValues::Zero => is_zero(),
+            LiteralValues::One => {
+                matches!(Decimal::from_lit(lit), Some(decimal) if decimal.is_one())
+            }
+            LiteralValues::NonZero => !is_zero(),
+            LiteralValues::Unsigned => matches!(
+                lit,
+                Lit::Int(lit) if matches!(lit.suffix(), "u8" | "u16" | "u32" | "u64" | "u128" | "usize")
+            ),
+        }
+    }
+}
+
+/// The attribute macros of this crate.
+#[derive(Copy, Clone)]
+enum AttributeKind {
+    Numeric(LiteralValues),
+    Float,
+    Int(LiteralValues),
+}
+
+impl AttributeKind {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "replace_numeric_literals" => Some(AttributeKind::Numeric(LiteralValues::All)),
+            "replace_zero_literals" => Some(AttributeKind::Numeric(LiteralValues::Zero)),
+            "replace_one_literals" => Some(AttributeKind::Numeric(LiteralValues::One)),
+            "replace_nonzero_literals" => Some(AttributeKind::Numeric(LiteralValues::NonZero)),
+            "replace_float_literals" => Some(AttributeKind::Float),
+            "replace_int_literals" => Some(AttributeKind::Int(LiteralValues::All)),
+            "replace_uint_literals" => Some(AttributeKind::Int(LiteralValues::Unsigned)),
+            _ => None,
+        }
+    }
+
+    /// Returns the kind of the attribute, if it is one of the attribute macros of this crate.
+    fn of(attr: &Attribute) -> Option<Self> {
+        let segment = attr.path.segments.last()?;
+        AttributeKind::from_name(&segment.ident.to_string())
+    }
+}
+
+/// Returns whether any of the attributes is one of the attribute macros of this crate, either by
+/// its own name or by one of the given aliases.
+///
+/// Nested items with such an attribute are skipped when visiting the decorated item, since
+/// their own attribute takes precedence over the attribute of the enclosing item (e.g. a module).
+fn has_replacement_attribute(attrs: &[Attribute], aliases: &[String]) -> bool {
+    attrs.iter().any(|attr| {
+        AttributeKind::of(attr).is_some()
+            || matches!(attr.path.segments.last(), Some(segment) if aliases.iter().any(|alias| segment.ident == alias))
+    })
+}
+
+/// Finds the names under which attribute macros of this crate are imported with `use ... as`.
+///
+/// Only imports within the decorated item can be found, and they are recognized by the name of
+/// the imported item alone, since the crate itself may have been renamed.
+struct AliasFinder {
+    pub aliases: Vec<String>,
+}
+
+impl<'ast> Visit<'ast> for AliasFinder {
+    fn visit_use_rename(&mut self, rename: &'ast UseRename) {
+        if AttributeKind::from_name(&rename.ident.to_string()).is_some() {
+            self.aliases.push(rename.rename.to_string());
+        }
+    }
+}
+
+/// Returns the names under which attribute macros of this crate are imported in the item.
+fn attribute_aliases(item: &Item) -> Vec<String> {
+    let mut finder = AliasFinder {
+        aliases: Vec::new(),
+    };
+    finder.visit_item(item);
+    finder.aliases
+}
+
+/// Returns the name of an item, or the name of its type for an `impl` block.
+fn item_name(item: &Item) -> String {
+    let ident = match item {
+        Item::Const(item) => &item.ident,
+        Item::Enum(item) => &item.ident,
+        Item::ExternCrate(item) => &item.ident,
+        Item::Fn(item) => &item.sig.ident,
+        Item::Impl(item) => return item.self_ty.to_token_stream().to_string(),
+        Item::Macro2(item) => &item.ident,
+        Item::Mod(item) => &item.ident,
+        Item::Static(item) => &item.ident,
+        Item::Struct(item) => &item.ident,
+        Item::Trait(item) => &item.ident,
+        Item::TraitAlias(item) => &item.ident,
+        Item::Type(item) => &item.ident,
+        Item::Union(item) => &item.ident,
+        Item::Macro(item) => match &item.ident {
+            Some(ident) => ident,
+            None => return "item".to_string(),
+        },
+        _ => return "item".to_string(),
+    };
+    ident.to_string()
+}
+
+/// Returns the attributes of an item.
+fn item_attrs(item: &Item) -> &[Attribute] {
+    match item {
+        Item::Const(item) => &item.attrs,
+        Item::Enum(item) => &item.attrs,
+        Item::ExternCrate(item) => &item.attrs,
+        Item::Fn(item) => &item.attrs,
+        Item::ForeignMod(item) => &item.attrs,
+        Item::Impl(item) => &item.attrs,
+        Item::Macro(item) => &item.attrs,
+        Item::Macro2(item) => &item.attrs,
+        Item::Mod(item) => &item.attrs,
+        Item::Static(item) => &item.attrs,
+        Item::Struct(item) => &item.attrs,
+        Item::Trait(item) => &item.attrs,
+        Item::TraitAlias(item) => &item.attrs,
+        Item::Type(item) => &item.attrs,
+        Item::Union(item) => &item.attrs,
+        Item::Use(item) => &item.attrs,
+        _ => &[],
+    }
+}
+
+/// Returns the attributes of an item, if it can have attributes.
+fn item_attrs_mut(item: &mut Item) -> Option<&mut Vec<Attribute>> {
+    match item {
+        Item::Const(item) => Some(&mut item.attrs),
+        Item::Enum(item) => Some(&mut item.attrs),
+        Item::ExternCrate(item) => Some(&mut item.attrs),
+        Item::Fn(item) => Some(&mut item.attrs),
+        Item::ForeignMod(item) => Some(&mut item.attrs),
+        Item::Impl(item) => Some(&mut item.attrs),
+        Item::Macro(item) => Some(&mut item.attrs),
+        Item::Macro2(item) => Some(&mut item.attrs),
+        Item::Mod(item) => Some(&mut item.attrs),
+        Item::Static(item) => Some(&mut item.attrs),
+        Item::Struct(item) => Some(&mut item.attrs),
+        Item::Trait(item) => Some(&mut item.attrs),
+        Item::TraitAlias(item) => Some(&mut item.attrs),
+        Item::Type(item) => Some(&mut item.attrs),
+        Item::Union(item) => Some(&mut item.attrs),
+        Item::Use(item) => Some(&mut item.attrs),
+        _ => None,
+    }
+}
+
+/// Argument that replaces the arguments of stacked attributes once they have been applied.
+///
+/// The attributes are kept on the item, so that their paths are still resolved (e.g. their
+/// imports are used), but their own expansion leaves the item unchanged.
+const APPLIED_MARKER: &str = "__numeric_literals_applied";
+
+/// Returns whether the arguments of an attribute are the marker of an applied attribute.
+fn is_applied(attr: &proc_macro2::TokenStream) -> bool {
+    let mut tokens = attr.clone().into_iter();
+    matches!(tokens.next(), Some(TokenTree::Ident(ident)) if ident == APPLIED_MARKER)
+        && tokens.next().is_none()
+}
+
+/// Takes the arguments of the attributes of this crate that are stacked on the item below the
+/// attribute being expanded, and marks those attributes as applied.
+fn take_stacked_attributes(
+    item: &mut Item,
+) -> Result<Vec<(AttributeKind, proc_macro2::TokenStream)>, syn::Error> {
+    let mut stacked = Vec::new();
+    for attr in item_attrs_mut(item).into_iter().flatten() {
+        if let Some(kind) = AttributeKind::of(attr) {
+            let args: proc_macro2::TokenStream = attr.parse_args()?;
+            if !is_applied(&args) {
+                stacked.push((kind, args));
+                let marker = Ident::new(APPLIED_MARKER, Span::call_site());
+                attr.tokens = quote!((#marker));
+            }
+        }
+    }
+    Ok(stacked)
+}
+
+/// Name of the attribute that marks instantiated replacements.
+///
+/// While the attributes stacked on an item are applied, the replacements of one attribute must
+/// not be visited by the following attributes. Each replacement is therefore wrapped in marked
+/// parentheses, which are removed from the expanded output.
+const REPLACED_MARKER: &str = "__numeric_literals_replaced";
+
+/// Wraps an instantiated replacement in marked parentheses.
+fn marked(expr: Expr) -> Expr {
+    let marker = Ident::new(REPLACED_MARKER, Span::call_site());
+    Expr::Paren(ExprParen {
+        attrs: vec![parse_quote!(#[#marker])],
+        paren_token: syn::token::Paren(Span::call_site()),
+        expr: Box::new(expr),
+    })
+}
+
+/// Name of the binding through which replacements are passed with `expansion_markers = true`.
+const EXPANSION_MARKER: &str = "__numeric_literal_replaced__";
+
+/// Binds a marked replacement to a variable named after `EXPANSION_MARKER`, which makes the
+/// replacements easy to find in the output of `cargo expand`. The binding is only visible to the
+/// replacement itself, so it cannot shadow any variable of the decorated item.
+fn with_expansion_marker(expr: Expr) -> Expr {
+    let binding = Ident::new(EXPANSION_MARKER, Span::mixed_site());
+    let replacement = unmarked(&expr);
+    marked(parse_quote!({
+        let #binding = #replacement;
+        #binding
+    }))
 }
 
-struct FloatLiteralVisitor<'a> {
-    pub parameters: MacroParameters,
-    pub placeholder: &'a str,
-    pub replacement: &'a Expr,
+/// Returns whether the expression is an instantiated replacement.
+fn is_marked(expr: &Expr) -> bool {
+    matches!(expr, Expr::Paren(paren) if paren.attrs.iter().any(|attr| attr.path.is_ident(REPLACED_MARKER)))
 }
 
-struct IntLiteralVisitor<'a> {
-    pub parameters: MacroParameters,
-    pub placeholder: &'a str,
-    pub replacement: &'a Expr,
+/// Returns the instantiated replacement in a marked expression, or the expression if it is not marked.
+fn unmarked(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Paren(paren) if is_marked(expr) => &paren.expr,
+        expr => expr,
+    }
+}
+
+/// Encloses an instantiated replacement in braces, for positions in which expressions other than
+/// literals and paths must be braced, such as const generic arguments and their defaults.
+fn braced_if_replaced(expr: &mut Expr) {
+    if is_marked(expr) {
+        let replacement = std::mem::replace(expr, Expr::Verbatim(proc_macro2::TokenStream::new()));
+        *expr = Expr::Block(ExprBlock {
+            attrs: Vec::new(),
+            label: None,
+            block: Block {
+                brace_token: syn::token::Brace(Span::call_site()),
+                stmts: vec![Stmt::Expr(replacement)],
+            },
+        });
+    }
+}
+
+/// Returns whether the tokens `#` and `group` form the marker of an instantiated replacement.
+fn is_marker_token(group: &Group) -> bool {
+    let mut tokens = group.stream().into_iter();
+    group.delimiter() == Delimiter::Bracket
+        && matches!(tokens.next(), Some(TokenTree::Ident(ident)) if ident == REPLACED_MARKER)
+        && tokens.next().is_none()
+}
+
+/// Removes the markers of instantiated replacements, along with their parentheses.
+///
+/// The replacements are already parenthesized where their precedence requires it, so the marked
+/// parentheses can be removed without changing the meaning of the expression.
+fn remove_markers(tokens: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    let mut output = proc_macro2::TokenStream::new();
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Punct(punct)
+                if punct.as_char() == '#'
+                    && matches!(tokens.peek(), Some(TokenTree::Group(group)) if is_marker_token(group)) =>
+            {
+                tokens.next();
+                if let Some(TokenTree::Group(parens)) = tokens.next() {
+                    output.extend(remove_markers(parens.stream()));
+                }
+            }
+            TokenTree::Group(group) => {
+                let mut stripped = Group::new(group.delimiter(), remove_markers(group.stream()));
+                stripped.set_span(group.span());
+                output.extend(Some(TokenTree::Group(stripped)));
+            }
+            token => output.extend(Some(token)),
+        }
+    }
+    output
 }
 
 /// Represents classes of primitive types relevant to the crate
@@ -161,160 +1124,2060 @@ enum PrimitiveClass {
     Other,
 }
 
-/// Returns what class of primitive types is represented by this literal expression, e.g. `20f64 -> Float`, `20 -> Int`
-fn determine_primitive_class(lit_expr: &ExprLit) -> PrimitiveClass {
-    match &lit_expr.lit {
-        // Parsed float literals are always floats
-        Lit::Float(_) => PrimitiveClass::Float,
-        // Literals like `20f64` are parsed as `LitInt`s
-        Lit::Int(int_lit) if matches!(int_lit.suffix(), "f32" | "f64") => PrimitiveClass::Float,
-        // All other integer literals should be actual integers
-        Lit::Int(_) => PrimitiveClass::Int,
-        _ => PrimitiveClass::Other,
+/// A primitive integer type, for checking that literals fit in it.
+#[derive(Copy, Clone)]
+struct IntegerType {
+    pub name: &'static str,
+    pub min: i128,
+    pub max: u128,
+}
+
+impl IntegerType {
+    /// Looks up a primitive integer type, where `isize` and `usize` are assumed to be 64 bits wide.
+    fn from_name(name: &str) -> Option<Self> {
+        let (name, min, max) = match name {
+            "i8" => ("i8", i8::MIN as i128, i8::MAX as u128),
+            "i16" => ("i16", i16::MIN as i128, i16::MAX as u128),
+            "i32" => ("i32", i32::MIN as i128, i32::MAX as u128),
+            "i64" => ("i64", i64::MIN as i128, i64::MAX as u128),
+            "i128" => ("i128", i128::MIN, i128::MAX as u128),
+            "isize" => ("isize", i64::MIN as i128, i64::MAX as u128),
+            "u8" => ("u8", 0, u8::MAX as u128),
+            "u16" => ("u16", 0, u16::MAX as u128),
+            "u32" => ("u32", 0, u32::MAX as u128),
+            "u64" => ("u64", 0, u64::MAX as u128),
+            "u128" => ("u128", 0, u128::MAX),
+            "usize" => ("usize", 0, u64::MAX as u128),
+            _ => return None,
+        };
+        Some(IntegerType { name, min, max })
     }
 }
 
-fn replace_literal(expr: &mut Expr, placeholder: &str, literal: &ExprLit) {
-    let mut replacer = ReplacementExpressionVisitor {
-        placeholder,
-        literal,
-    };
-    replacer.visit_expr_mut(expr);
+/// A primitive numeric type given as the replacement, whose suffix is attached to the literals
+/// instead of replacing them with an expression.
+#[derive(Copy, Clone)]
+enum PrimitiveSuffix {
+    Float(&'static str),
+    Int(IntegerType),
 }
 
-fn try_parse_punctuated_macro<P: ToTokens, V: VisitMut, F: Parser<Output = Punctuated<Expr, P>>>(
+impl PrimitiveSuffix {
+    /// Returns the primitive type if the replacement is just its name, e.g. `f32`.
+    fn from_replacement(replacement: &Replacement) -> Option<Self> {
+        let path = match replacement {
+            Replacement::Single(expr) => match &**expr {
+                Expr::Path(path_expr)
+                    if path_expr.qself.is_none() && path_expr.attrs.is_empty() =>
+                {
+                    &path_expr.path
+                }
+                _ => return None,
+            },
+            Replacement::Routed(_) => return None,
+        };
+        match path.get_ident()?.to_string().as_str() {
+            "f32" => Some(PrimitiveSuffix::Float("f32")),
+            "f64" => Some(PrimitiveSuffix::Float("f64")),
+            name => IntegerType::from_name(name).map(PrimitiveSuffix::Int),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            PrimitiveSuffix::Float(name) => name,
+            PrimitiveSuffix::Int(integer_type) => integer_type.name,
+        }
+    }
+
+    /// Returns the literal with its suffix replaced by the name of the type.
+    ///
+    /// Integer literals may be given a float suffix if they are written in decimal, while float
+    /// literals cannot be given an integer suffix.
+    fn apply(self, lit: &Lit) -> Result<Lit, syn::Error> {
+        let (kind, suffix) = match lit {
+            Lit::Float(float_lit) => ("float", float_lit.suffix()),
+            Lit::Int(int_lit) if matches!(int_lit.suffix(), "f32" | "f64") => {
+                ("float", int_lit.suffix())
+            }
+            Lit::Int(int_lit) => ("integer", int_lit.suffix()),
+            _ => return Ok(lit.clone()),
+        };
+        let text = lit.to_token_stream().to_string();
+        let digits = &text[..text.len() - suffix.len()];
+        let error = match self {
+            PrimitiveSuffix::Int(_) if kind == "float" => Some(""),
+            PrimitiveSuffix::Float(_)
+                if digits.starts_with("0x")
+                    || digits.starts_with("0o")
+                    || digits.starts_with("0b") =>
+            {
+                Some(", since it is not written in decimal")
+            }
+            _ => None,
+        };
+        if let Some(reason) = error {
+            let message = format!(
+                "{} literal `{}` cannot be given the suffix `{}`{}",
+                kind,
+                text,
+                self.name(),
+                reason
+            );
+            return Err(syn::Error::new(lit.span(), message));
+        }
+        let mut suffixed: proc_macro2::Literal =
+            format!("{}{}", digits, self.name()).parse().map_err(|_| {
+                syn::Error::new(lit.span(), "failed to attach the suffix to the literal")
+            })?;
+        suffixed.set_span(synthesized_span(lit));
+        Ok(Lit::new(suffixed))
+    }
+}
+
+/// What to do with macro bodies that cannot be parsed as expressions.
+#[derive(Copy, Clone)]
+enum MacroFallback {
+    /// Leave the body untouched
+    Skip,
+    /// Replace numeric literal tokens in the body on a best-effort basis
+    Tokens,
+    /// Emit an error
+    Error,
+}
+
+impl MacroFallback {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "skip" => Some(MacroFallback::Skip),
+            "tokens" => Some(MacroFallback::Tokens),
+            "error" => Some(MacroFallback::Error),
+            _ => None,
+        }
+    }
+}
+
+/// Where literals are replaced, by the expression that they are part of.
+#[derive(Copy, Clone, PartialEq)]
+enum LiteralContext {
+    /// Literals are replaced anywhere
+    All,
+    /// Literals are only replaced where they plausibly take part in arithmetic, see
+    /// `LiteralVisitor::visit_operand_mut`
+    Arithmetic,
+}
+
+impl LiteralContext {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "all" => Some(LiteralContext::All),
+            "arithmetic" => Some(LiteralContext::Arithmetic),
+            _ => None,
+        }
+    }
+}
+
+/// How the body of a macro registered with the `macro_parsers` parameter is visited.
+#[derive(Copy, Clone)]
+enum MacroParser {
+    /// An expression or a list of expressions separated by `,` or `;`, like `vec!`
+    Expressions,
+    /// A format string followed by arguments, like `format!`
+    Format,
+    /// An expression followed by a pattern, like `matches!`
+    Pattern,
+    /// Numeric literal tokens, like with `macro_fallback = tokens`
+    Tokens,
+    /// Leave the body untouched
+    Skip,
+}
+
+impl MacroParser {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "expressions" => Some(MacroParser::Expressions),
+            "format" => Some(MacroParser::Format),
+            "pattern" => Some(MacroParser::Pattern),
+            "tokens" => Some(MacroParser::Tokens),
+            "skip" => Some(MacroParser::Skip),
+            _ => None,
+        }
+    }
+}
+
+/// The value of a possibly negated integer literal.
+#[derive(Copy, Clone)]
+struct IntegerValue {
+    pub negative: bool,
+    pub magnitude: u128,
+}
+
+impl IntegerValue {
+    fn new(negative: bool, magnitude: u128) -> Self {
+        Self {
+            negative: negative && magnitude != 0,
+            magnitude,
+        }
+    }
+
+    fn at_least(self, bound: i128) -> bool {
+        match (self.negative, bound < 0) {
+            (false, true) => true,
+            (true, false) => false,
+            (false, false) => self.magnitude >= bound.unsigned_abs(),
+            (true, true) => self.magnitude <= bound.unsigned_abs(),
+        }
+    }
+
+    fn at_most(self, bound: i128) -> bool {
+        match (self.negative, bound < 0) {
+            (false, true) => false,
+            (true, false) => true,
+            (false, false) => self.magnitude <= bound.unsigned_abs(),
+            (true, true) => self.magnitude >= bound.unsigned_abs(),
+        }
+    }
+}
+
+impl std::fmt::Display for IntegerValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", self.magnitude)
+    }
+}
+
+/// Returns what class of primitive types is represented by this literal expression, e.g. `20f64 -> Float`, `20 -> Int`
+fn determine_primitive_class(lit_expr: &ExprLit) -> PrimitiveClass {
+    match &lit_expr.lit {
+        // Parsed float literals are always floats
+        Lit::Float(_) => PrimitiveClass::Float,
+        // Literals like `20f64` are parsed as `LitInt`s
+        Lit::Int(int_lit) if matches!(int_lit.suffix(), "f32" | "f64") => PrimitiveClass::Float,
+        // All other integer literals should be actual integers
+        Lit::Int(_) => PrimitiveClass::Int,
+        _ => PrimitiveClass::Other,
+    }
+}
+
+/// Span for tokens synthesized from a literal.
+///
+/// The span points at the literal, but resolves as macro output so that lints about
+/// the source text of the literal (e.g. leading zeros) are not applied to the new tokens.
+fn synthesized_span(lit: &Lit) -> Span {
+    Span::call_site().located_at(lit.span())
+}
+
+/// The kind of the literal as it is reported, `float` or `integer`.
+fn kind_name(literal: &ExprLit) -> &'static str {
+    match determine_primitive_class(literal) {
+        PrimitiveClass::Float => "float",
+        _ => "integer",
+    }
+}
+
+/// Rewrites an unsuffixed integer literal as the equivalent float literal, e.g. `2` as `2.0`.
+fn promote_int(lit_expr: &mut ExprLit) {
+    if let Lit::Int(lit) = &lit_expr.lit {
+        if lit.suffix().is_empty() {
+            let digits = format!("{}.0", lit.base10_digits());
+            lit_expr.lit = Lit::Float(LitFloat::new(&digits, lit.span()));
+        }
+    }
+}
+
+/// A warning that reports the replacement of the literal for the `trace` parameter.
+///
+/// The warning is reported at the literal, so the compiler shows its location.
+fn trace_warning(literal: &ExprLit) -> Warning {
+    let kind = kind_name(literal);
+    Warning::new(
+        literal.lit.span(),
+        format!(
+            "replaced {} literal `{}`",
+            kind,
+            literal.lit.to_token_stream()
+        ),
+    )
+}
+
+/// A warning that reports the replacement that would be made for the `dry_run` parameter.
+fn dry_run_warning(literal: &ExprLit, replacement: &Expr) -> Warning {
+    Warning::new(
+        literal.lit.span(),
+        format!(
+            "would replace {} literal `{}` with `{}`",
+            kind_name(literal),
+            literal.lit.to_token_stream(),
+            unmarked(replacement).to_token_stream()
+        ),
+    )
+}
+
+/// Selects the replacement expression for the literal and replaces the placeholders in it.
+///
+/// The tokens of the instantiated replacement are located at the literal, see
+/// `Template::instantiate`.
+fn instantiate_replacement(
+    replacement: &Replacement<Template>,
+    literal: &ExprLit,
+    index: usize,
+    item_name: &str,
+    parameters: &MacroParameters,
+) -> Result<Expr, syn::Error> {
+    let substitution = LiteralSubstitution {
+        literal,
+        index,
+        item_name,
+        parameters,
+    };
+    let tokens = replacement
+        .select(literal)?
+        .instantiate(literal.lit.span(), |placeholder| {
+            substitution
+                .substitute(placeholder)
+                .map(ToTokens::into_token_stream)
+        })?;
+    Ok(marked(Expr::Verbatim(tokens)))
+}
+
+/// An expression in the body of a macro, along with the tokens it was parsed from.
+struct MacroArgument {
+    expr: Expr,
+    tokens: proc_macro2::TokenStream,
+}
+
+impl Parse for MacroArgument {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let begin = input.fork();
+        let expr = input.parse()?;
+        let mut tokens = proc_macro2::TokenStream::new();
+        while begin.cursor() != input.cursor() {
+            tokens.extend(Some(begin.parse::<TokenTree>()?));
+        }
+        Ok(MacroArgument { expr, tokens })
+    }
+}
+
+/// A visitor that counts the literals it visits for replacement.
+trait CountsReplacements {
+    /// The number of literals visited for replacement so far.
+    fn replacement_count(&self) -> usize;
+}
+
+impl MacroArgument {
+    /// Visits the expression and returns the resulting tokens.
+    ///
+    /// Macro invocations nested in the expression are visited as part of it, and their bodies
+    /// are rewritten in place, so that the returned tokens include the rewritten bodies at any
+    /// depth of nesting. If the visitor replaces no literal in the expression, the original tokens
+    /// are returned as they were written, so that their spans and grouping are exactly preserved.
+    fn visit<V: VisitMut + CountsReplacements>(
+        mut self,
+        visitor: &mut V,
+    ) -> proc_macro2::TokenStream {
+        let count = visitor.replacement_count();
+        visitor.visit_expr_mut(&mut self.expr);
+        if visitor.replacement_count() == count {
+            self.tokens
+        } else {
+            self.expr.into_token_stream()
+        }
+    }
+}
+
+/// A separator between the expressions in the body of a macro.
+enum MacroSeparator {
+    Comma(Token![,]),
+    Semi(Token![;]),
+}
+
+impl ToTokens for MacroSeparator {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        match self {
+            MacroSeparator::Comma(comma) => comma.to_tokens(tokens),
+            MacroSeparator::Semi(semi) => semi.to_tokens(tokens),
+        }
+    }
+}
+
+/// Parses the body of the macro as a single expression or a list of expressions separated by
+/// either `,` or `;`, allowing a trailing separator, and visits the expressions.
+///
+/// The body is parsed in a single pass, and all separators of the list must be the same, as in
+/// `vec![1.0, 2.0]` and `vec![0.0; 3]`.
+fn try_parse_expression_list_macro<V: VisitMut + CountsReplacements>(
+    visitor: &mut V,
+    mac: &mut Macro,
+) -> bool {
+    let parser = |input: ParseStream| {
+        let mut arguments = Vec::new();
+        let mut is_comma = None;
+        while !input.is_empty() {
+            let argument: MacroArgument = input.parse()?;
+            let separator = if input.is_empty() {
+                None
+            } else if input.peek(Token![,]) && is_comma != Some(false) {
+                is_comma = Some(true);
+                Some(MacroSeparator::Comma(input.parse()?))
+            } else if input.peek(Token![;]) && is_comma != Some(true) {
+                is_comma = Some(false);
+                Some(MacroSeparator::Semi(input.parse()?))
+            } else {
+                return Err(input.error("expected a separator"));
+            };
+            arguments.push((argument, separator));
+        }
+        Ok(arguments)
+    };
+    if let Ok(arguments) = mac.parse_body_with(parser) {
+        let mut tokens = proc_macro2::TokenStream::new();
+        for (argument, separator) in arguments {
+            tokens.extend(argument.visit(visitor));
+            separator.to_tokens(&mut tokens);
+        }
+        mac.tokens = tokens;
+        return true;
+    }
+    false
+}
+
+/// Returns whether the macro is an inline assembly macro, like `asm!`. The operands and
+/// templates of these macros must never be replaced, so their bodies are not visited at all.
+fn is_asm_macro(mac: &Macro) -> bool {
+    match mac.path.segments.last() {
+        Some(segment) => {
+            segment.ident == "asm" || segment.ident == "global_asm" || segment.ident == "naked_asm"
+        }
+        None => false,
+    }
+}
+
+/// Returns whether the macro is `offset_of!`, whose body consists of a type and a path of
+/// fields, in which tuple fields are written as integer literals.
+fn is_offset_of_macro(mac: &Macro) -> bool {
+    matches!(mac.path.segments.last(), Some(segment) if segment.ident == "offset_of")
+}
+
+/// Returns whether the macro takes a pattern as its second argument, like `matches!`.
+fn is_pattern_macro(mac: &Macro) -> bool {
+    match mac.path.segments.last() {
+        Some(segment) => {
+            segment.ident == "matches"
+                || segment.ident == "assert_matches"
+                || segment.ident == "debug_assert_matches"
+        }
+        None => false,
+    }
+}
+
+/// Returns the number of arguments preceding the format string of a formatting macro (e.g. the
+/// destination of `write!`), or `None` if the macro is not a formatting macro.
+fn format_string_position(mac: &Macro) -> Option<usize> {
+    let name = mac.path.segments.last()?.ident.to_string();
+    match name.as_str() {
+        "format" | "format_args" | "print" | "println" | "eprint" | "eprintln" | "panic"
+        | "unreachable" | "todo" | "unimplemented" => Some(0),
+        "write" | "writeln" => Some(1),
+        _ => None,
+    }
+}
+
+/// Visits the body of a formatting macro, such as `format!` or `write!`.
+///
+/// The arguments up to and including the format string are left untouched. Only the expressions
+/// of the trailing arguments are visited, including those of named arguments like `x = 1.5`.
+fn try_parse_format_macro<V: VisitMut + CountsReplacements>(
+    visitor: &mut V,
+    mac: &mut Macro,
+    position: usize,
+) -> bool {
+    let parser = |input: ParseStream| {
+        let mut leading = proc_macro2::TokenStream::new();
+        for _ in 0..=position {
+            if input.is_empty() {
+                break;
+            }
+            leading.extend(input.parse::<MacroArgument>()?.tokens);
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?.to_tokens(&mut leading);
+            }
+        }
+        let mut arguments = Vec::new();
+        while !input.is_empty() {
+            let name =
+                if input.peek(syn::Ident) && input.peek2(Token![=]) && !input.peek2(Token![==]) {
+                    Some((input.parse::<syn::Ident>()?, input.parse::<Token![=]>()?))
+                } else {
+                    None
+                };
+            let argument: MacroArgument = input.parse()?;
+            let separator = if input.is_empty() {
+                None
+            } else {
+                Some(input.parse::<Token![,]>()?)
+            };
+            arguments.push((name, argument, separator));
+        }
+        Ok((leading, arguments))
+    };
+    if let Ok((mut tokens, arguments)) = mac.parse_body_with(parser) {
+        for (name, argument, separator) in arguments {
+            if let Some((name, eq_token)) = name {
+                name.to_tokens(&mut tokens);
+                eq_token.to_tokens(&mut tokens);
+            }
+            tokens.extend(argument.visit(visitor));
+            separator.to_tokens(&mut tokens);
+        }
+        mac.tokens = tokens;
+        return true;
+    }
+    false
+}
+
+/// Returns whether the macro is `json!` from `serde_json`.
+fn is_json_macro(mac: &Macro) -> bool {
+    matches!(mac.path.segments.last(), Some(segment) if segment.ident == "json")
+}
+
+/// Visits the body of a macro of the form `matches!(expr, pattern if guard, args...)`.
+///
+/// The pattern is left untouched, since replacing literals in it would not produce a valid
+/// pattern. The scrutinee, the guard and any further arguments are visited.
+fn try_parse_pattern_macro<V: VisitMut + CountsReplacements>(
+    visitor: &mut V,
+    mac: &mut Macro,
+) -> bool {
+    let parser = |input: ParseStream| {
+        let scrutinee: MacroArgument = input.parse()?;
+        let comma: Token![,] = input.parse()?;
+        let mut pattern = proc_macro2::TokenStream::new();
+        while !input.is_empty() && !input.peek(Token![if]) && !input.peek(Token![,]) {
+            pattern.extend(Some(input.parse::<TokenTree>()?));
+        }
+        let guard = if input.peek(Token![if]) {
+            Some((
+                input.parse::<Token![if]>()?,
+                input.parse::<MacroArgument>()?,
+            ))
+        } else {
+            None
+        };
+        let mut arguments = Vec::new();
+        while !input.is_empty() {
+            let separator: Token![,] = input.parse()?;
+            let argument = if input.is_empty() {
+                None
+            } else {
+                Some(input.parse::<MacroArgument>()?)
+            };
+            arguments.push((separator, argument));
+        }
+        Ok((scrutinee, comma, pattern, guard, arguments))
+    };
+    if let Ok((scrutinee, comma, pattern, guard, arguments)) = mac.parse_body_with(parser) {
+        let mut tokens = scrutinee.visit(visitor);
+        comma.to_tokens(&mut tokens);
+        tokens.extend(pattern);
+        if let Some((if_token, guard)) = guard {
+            if_token.to_tokens(&mut tokens);
+            tokens.extend(guard.visit(visitor));
+        }
+        for (separator, argument) in arguments {
+            separator.to_tokens(&mut tokens);
+            if let Some(argument) = argument {
+                tokens.extend(argument.visit(visitor));
+            }
+        }
+        mac.tokens = tokens;
+        return true;
+    }
+    false
+}
+
+/// Visits the numeric literal tokens in the transcribers of the rules of a `macro_rules!`
+/// definition, such as `{ $x * 2.0 }` in `($x:expr) => { $x * 2.0 };`. This is only done for the
+/// definition that the attribute is placed on.
+///
+/// The matchers are left untouched. Metavariables and repetitions in the transcribers consist of
+/// other tokens than literals, so they are left untouched as well.
+fn visit_macro_rules_mut<V: VisitMut>(visitor: &mut V, mac: &mut Macro) {
+    let mut output = proc_macro2::TokenStream::new();
+    // Whether the previous tokens are `=>`, which precedes the transcriber of a rule
+    let (mut after_eq, mut after_arrow) = (false, false);
+    for token in mac.tokens.clone() {
+        let token = match token {
+            TokenTree::Group(group) if after_arrow => {
+                let stream = visit_literal_tokens_mut(visitor, group.stream());
+                let mut visited = Group::new(group.delimiter(), stream);
+                visited.set_span(group.span());
+                TokenTree::Group(visited)
+            }
+            token => token,
+        };
+        after_arrow = after_eq && matches!(&token, TokenTree::Punct(p) if p.as_char() == '>');
+        after_eq = matches!(&token, TokenTree::Punct(p) if p.as_char() == '=' && p.spacing() == Spacing::Joint);
+        output.extend(Some(token));
+    }
+    mac.tokens = output;
+}
+
+/// Visits the numeric literal tokens in the tokens of a macro body that cannot be parsed.
+///
+/// Each literal is visited as an expression of its own, and the result is emitted in an
+/// invisible group so that it keeps its precedence. Attributes and tuple fields, such as the `0`
+/// in `pair.0`, are left untouched.
+fn visit_literal_tokens_mut<V: VisitMut>(
+    visitor: &mut V,
+    tokens: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    visit_literal_tokens_in_group_mut(visitor, tokens, Delimiter::None)
+}
+
+/// Visits the numeric literal tokens in the tokens of a group with the given delimiter.
+///
+/// The tokens after a `;` in brackets are visited as the length of an array, as in `[0.0; 4]`.
+fn visit_literal_tokens_in_group_mut<V: VisitMut>(
+    visitor: &mut V,
+    tokens: proc_macro2::TokenStream,
+    delimiter: Delimiter,
+) -> proc_macro2::TokenStream {
+    let mut output = proc_macro2::TokenStream::new();
+    let mut tokens = tokens.into_iter().peekable();
+    let mut in_length = false;
+    // Whether the previous token is a single `.`, as opposed to the `..` of a range
+    let (mut after_dot, mut after_dots) = (false, false);
+    while let Some(token) = tokens.next() {
+        let is_dot = matches!(&token, TokenTree::Punct(p) if p.as_char() == '.');
+        let field = after_dot && !after_dots;
+        after_dots = after_dot && is_dot;
+        after_dot = is_dot;
+        match token {
+            TokenTree::Punct(punct)
+                if punct.as_char() == ';' && delimiter == Delimiter::Bracket =>
+            {
+                in_length = true;
+                output.extend(Some(TokenTree::Punct(punct)));
+            }
+            TokenTree::Punct(punct) if punct.as_char() == '#' => {
+                output.extend(Some(TokenTree::Punct(punct)));
+                if matches!(tokens.peek(), Some(TokenTree::Punct(p)) if p.as_char() == '!') {
+                    output.extend(tokens.next());
+                }
+                let is_marker =
+                    matches!(tokens.peek(), Some(TokenTree::Group(g)) if is_marker_token(g));
+                if matches!(tokens.peek(), Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Bracket)
+                {
+                    output.extend(tokens.next());
+                }
+                // Instantiated replacements are not visited again
+                if is_marker {
+                    output.extend(tokens.next());
+                }
+            }
+            TokenTree::Group(group) => {
+                let stream =
+                    visit_literal_tokens_in_group_mut(visitor, group.stream(), group.delimiter());
+                let mut visited = Group::new(group.delimiter(), stream);
+                visited.set_span(group.span());
+                output.extend(Some(TokenTree::Group(visited)));
+            }
+            TokenTree::Literal(literal) if field => {
+                output.extend(Some(TokenTree::Literal(literal)))
+            }
+            TokenTree::Literal(literal) => {
+                let span = literal.span();
+                let mut expr = Expr::Lit(ExprLit {
+                    attrs: Vec::new(),
+                    lit: Lit::new(literal),
+                });
+                if in_length {
+                    // The literal is visited as the length of an array, so that the visitor
+                    // applies the same rules as to the lengths of array expressions
+                    let mut repeat: Expr = parse_quote!([(); #expr]);
+                    visitor.visit_expr_mut(&mut repeat);
+                    if let Expr::Repeat(repeat) = repeat {
+                        expr = *repeat.len;
+                    }
+                } else {
+                    visitor.visit_expr_mut(&mut expr);
+                }
+                if let Expr::Lit(lit_expr) = expr {
+                    lit_expr.to_tokens(&mut output);
+                } else {
+                    let mut group = Group::new(Delimiter::None, expr.into_token_stream());
+                    group.set_span(span);
+                    output.extend(Some(TokenTree::Group(group)));
+                }
+            }
+            token => output.extend(Some(token)),
+        }
+    }
+    output
+}
+
+/// Visits the body of the macro, if it can be parsed as expressions, or otherwise according
+/// to the fallback.
+///
+/// Returns whether the body was visited.
+fn visit_macros_mut<V: VisitMut + CountsReplacements>(
     visitor: &mut V,
     mac: &mut Macro,
-    parser: F,
+    parameters: &MacroParameters,
 ) -> bool {
-    if let Ok(mut exprs) = mac.parse_body_with(parser) {
-        exprs
-            .iter_mut()
-            .for_each(|expr| visitor.visit_expr_mut(expr));
-        mac.tokens = exprs.into_token_stream();
+    // Handle macros with a parser registered by the user, which takes precedence over the
+    // built-in handling of the macro
+    if let Some(parser) = parameters.macro_parser(mac) {
+        return match parser {
+            MacroParser::Expressions => try_parse_expression_list_macro(visitor, mac),
+            MacroParser::Format if !parameters.visit_format_args => true,
+            MacroParser::Format => try_parse_format_macro(visitor, mac, 0),
+            MacroParser::Pattern => try_parse_pattern_macro(visitor, mac),
+            MacroParser::Tokens => {
+                mac.tokens = visit_literal_tokens_mut(visitor, mac.tokens.clone());
+                true
+            }
+            MacroParser::Skip => true,
+        };
+    }
+
+    // Leave `macro_rules!` definitions untouched. Their transcribers are only visited if the
+    // attribute is placed on the definition itself, since they may use literals in positions
+    // where a replacement is not allowed.
+    if mac.path.is_ident("macro_rules") {
         return true;
     }
-    return false;
-}
 
-fn visit_macros_mut<V: VisitMut>(visitor: &mut V, mac: &mut Macro) {
-    // Handle expression based macros (e.g. assert)
-    if let Ok(mut expr) = mac.parse_body::<Expr>() {
-        visitor.visit_expr_mut(&mut expr);
-        mac.tokens = expr.into_token_stream();
-        return;
+    // Handle macros with a pattern argument (e.g. matches), which must not be visited as an
+    // expression. The token fallback would replace literals in the pattern, so it is not used.
+    if is_pattern_macro(mac) {
+        return try_parse_pattern_macro(visitor, mac);
+    }
+
+    // Handle formatting macros (e.g. format, write), whose format string must not be visited
+    if let Some(position) = format_string_position(mac) {
+        if !parameters.visit_format_args || try_parse_format_macro(visitor, mac, position) {
+            return true;
+        }
+    }
+
+    // Handle expression based macros (e.g. assert), and macros with expressions separated by
+    // , (e.g. vec with list, assert_eq) or ; (e.g. vec with repeat)
+    if try_parse_expression_list_macro(visitor, mac) {
+        return true;
+    }
+
+    // The body of `json!` consists of JSON-like token trees, in which literals are values
+    if is_json_macro(mac) || matches!(parameters.macro_fallback, MacroFallback::Tokens) {
+        mac.tokens = visit_literal_tokens_mut(visitor, mac.tokens.clone());
+        return true;
+    }
+    false
+}
+
+/// Returns whether the tokens contain a literal of a class that is replaced.
+fn contains_replaced_literal<F: Fn(PrimitiveClass) -> bool>(
+    tokens: proc_macro2::TokenStream,
+    is_replaced: &F,
+) -> bool {
+    let mut tokens = tokens.into_iter();
+    while let Some(token) = tokens.next() {
+        let found = match token {
+            // The literals of instantiated replacements are not replaced again
+            TokenTree::Group(group) if is_marker_token(&group) => {
+                tokens.next();
+                false
+            }
+            TokenTree::Group(group) => contains_replaced_literal(group.stream(), is_replaced),
+            TokenTree::Literal(literal) => is_replaced(determine_primitive_class(&ExprLit {
+                attrs: Vec::new(),
+                lit: Lit::new(literal),
+            })),
+            _ => false,
+        };
+        if found {
+            return true;
+        }
+    }
+    false
+}
+
+/// Reports a macro whose body could not be visited, if the body contains literals that would
+/// otherwise have been replaced. This is an error with `macro_fallback = error`, and a warning
+/// with `strict`. Otherwise the macro is skipped silently.
+fn report_skipped_macro<F: Fn(PrimitiveClass) -> bool>(
+    mac: &Macro,
+    fallback: MacroFallback,
+    strict: bool,
+    is_replaced: F,
+    errors: &mut Vec<syn::Error>,
+    warnings: &mut Vec<Warning>,
+) {
+    if !contains_replaced_literal(mac.tokens.clone(), &is_replaced) {
+        return;
+    }
+    let name = path_to_string(&mac.path);
+    let span = mac.path.span();
+    match fallback {
+        MacroFallback::Error => errors.push(syn::Error::new(
+            span,
+            format!(
+                "numeric literals in `{}!` cannot be replaced, because its body could not be parsed as expressions",
+                name
+            ),
+        )),
+        MacroFallback::Skip | MacroFallback::Tokens if strict => warnings.push(Warning::new(
+            span,
+            format!(
+                "numeric literals in `{}!` were not replaced, because its body could not be parsed as expressions",
+                name
+            ),
+        )),
+        MacroFallback::Skip | MacroFallback::Tokens => {}
+    }
+}
+
+/// Describes why a float literal is not exactly representable in the given format.
+fn inexact_message(lit: &Lit, decimal: &Decimal, format: FloatFormat) -> String {
+    let literal = lit.to_token_stream().to_string();
+    match decimal.nearest(format) {
+        Some(nearest) => format!(
+            "float literal `{}` is not exactly representable as {}, the nearest representable value is {}",
+            literal,
+            format.name(),
+            nearest.to_float_text()
+        ),
+        None => format!(
+            "float literal `{}` is out of range for {}",
+            literal,
+            format.name()
+        ),
+    }
+}
+
+/// Applies the `ignore_suffixed` and `deny_suffixed` parameters to a literal.
+///
+/// Returns whether the literal should be replaced.
+fn check_suffix(lit: &Lit, parameters: &MacroParameters, errors: &mut Vec<syn::Error>) -> bool {
+    let suffix = match lit {
+        Lit::Float(float_lit) => float_lit.suffix(),
+        Lit::Int(int_lit) => int_lit.suffix(),
+        _ => "",
+    };
+    if suffix.is_empty() {
+        true
+    } else if parameters.deny_suffixed {
+        let message = format!(
+            "literal `{}` has suffix `{}`, which is not allowed with `deny_suffixed = true`",
+            lit.to_token_stream(),
+            suffix
+        );
+        errors.push(syn::Error::new(lit.span(), message));
+        false
+    } else {
+        !parameters.ignore_suffixed
+    }
+}
+
+/// Returns whether the operator is a shift, whose right-hand side is an amount of bits.
+fn is_shift(op: &BinOp) -> bool {
+    matches!(
+        op,
+        BinOp::Shl(_) | BinOp::Shr(_) | BinOp::ShlEq(_) | BinOp::ShrEq(_)
+    )
+}
+
+/// Returns whether the operator is arithmetic or a comparison, whose operands plausibly take part
+/// in generic math.
+fn is_arithmetic(op: &BinOp) -> bool {
+    matches!(
+        op,
+        BinOp::Add(_)
+            | BinOp::Sub(_)
+            | BinOp::Mul(_)
+            | BinOp::Div(_)
+            | BinOp::Rem(_)
+            | BinOp::AddEq(_)
+            | BinOp::SubEq(_)
+            | BinOp::MulEq(_)
+            | BinOp::DivEq(_)
+            | BinOp::RemEq(_)
+            | BinOp::Eq(_)
+            | BinOp::Ne(_)
+            | BinOp::Lt(_)
+            | BinOp::Le(_)
+            | BinOp::Gt(_)
+            | BinOp::Ge(_)
+    )
+}
+
+/// Returns whether the expression is a literal, possibly negated or in parentheses.
+fn is_literal(expr: &Expr) -> bool {
+    match expr {
+        Expr::Lit(_) => true,
+        Expr::Paren(ExprParen { expr, .. })
+        | Expr::Group(ExprGroup { expr, .. })
+        | Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => is_literal(expr),
+        _ => false,
+    }
+}
+
+/// Evaluates an expression that consists solely of unsuffixed float literals, parentheses and the
+/// operators `+`, `-`, `*` and `/` in `f64`, or returns `None` for any other expression.
+fn fold_float(expr: &Expr) -> Option<f64> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Float(lit),
+            ..
+        }) if lit.suffix().is_empty() => lit.base10_parse().ok(),
+        Expr::Paren(ExprParen { expr, .. }) | Expr::Group(ExprGroup { expr, .. }) => {
+            fold_float(expr)
+        }
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => fold_float(expr).map(|value| -value),
+        Expr::Binary(binary) => {
+            let (left, right) = (fold_float(&binary.left)?, fold_float(&binary.right)?);
+            match binary.op {
+                BinOp::Add(_) => Some(left + right),
+                BinOp::Sub(_) => Some(left - right),
+                BinOp::Mul(_) => Some(left * right),
+                BinOp::Div(_) => Some(left / right),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Returns the integer literal negated by the expression, if it is of the form `-literal`.
+fn negated_int_literal(expr: &Expr) -> Option<&ExprLit> {
+    if let Expr::Unary(ExprUnary {
+        op: UnOp::Neg(_),
+        expr: inner,
+        ..
+    }) = expr
+    {
+        if let Expr::Lit(lit_expr) = &**inner {
+            if let PrimitiveClass::Int = determine_primitive_class(lit_expr) {
+                return Some(lit_expr);
+            }
+        }
+    }
+    None
+}
+
+impl<'a> LiteralVisitor<'a> {
+    /// Returns whether literals of the class are replaced, regardless of their value.
+    fn replaces(&self, class: PrimitiveClass) -> bool {
+        match class {
+            PrimitiveClass::Float => self.float_replacement.is_some(),
+            PrimitiveClass::Int => self.int_replacement.is_some() || self.promotes_ints(),
+            PrimitiveClass::Other => false,
+        }
+    }
+
+    /// Replaces the expression with a single float literal if it consists solely of float literals
+    /// and arithmetic operators, with `fold_constants`. Returns whether it was folded.
+    fn fold_constants(&mut self, expr: &mut Expr) -> bool {
+        if !self.parameters.fold_constants
+            || self.float_replacement.is_none()
+            || !matches!(self.values, LiteralValues::All)
+            || self.in_length
+            || self.in_skipped_call
+            || !self.parameters.only_in_calls.is_empty() && !self.in_listed_call
+            || !self.parameters.only_in_bindings.is_empty() && !self.in_listed_binding
+            || is_literal(expr)
+        {
+            return false;
+        }
+        let value = match fold_float(expr) {
+            Some(value) => value,
+            None => return false,
+        };
+        if !value.is_finite() {
+            self.errors.push(syn::Error::new_spanned(
+                &*expr,
+                format!(
+                    "`{}` evaluates to {} in `f64`, so it cannot be folded",
+                    expr.to_token_stream(),
+                    value
+                ),
+            ));
+            return false;
+        }
+        let span = expr.span();
+        let lit = Expr::Lit(ExprLit {
+            attrs: Vec::new(),
+            lit: Lit::Float(LitFloat::new(&format!("{:?}", value.abs()), span)),
+        });
+        let folded = if value.is_sign_negative() {
+            Expr::Unary(ExprUnary {
+                attrs: Vec::new(),
+                op: UnOp::Neg(Token![-](span)),
+                expr: Box::new(lit),
+            })
+        } else {
+            lit
+        };
+        // Parentheses around the expression are kept, since a negated literal does not bind as
+        // tightly as the parentheses, e.g. in `(1.0 - 2.0).abs()`
+        match expr {
+            Expr::Paren(ExprParen { expr: inner, .. })
+            | Expr::Group(ExprGroup { expr: inner, .. }) => **inner = folded,
+            _ => *expr = folded,
+        }
+        true
+    }
+
+    /// Whether unsuffixed integer literals are replaced as float literals, with `promote_ints`.
+    fn promotes_ints(&self) -> bool {
+        self.parameters.promote_ints
+            && !self.in_length
+            && self.int_replacement.is_none()
+            && self.float_replacement.is_some()
+    }
+
+    /// Reports the literal if it is not exactly representable in binary floating point,
+    /// depending on the exactness parameters.
+    fn check_exactness(&mut self, lit_expr: &ExprLit) {
+        let parameters = self.parameters;
+        let check_f32 = parameters.deny_inexact_f32 || parameters.warn_inexact_f32;
+        if !check_f32 && !parameters.warn_inexact {
+            return;
+        }
+        let lit = &lit_expr.lit;
+        let decimal = match Decimal::from_lit(lit) {
+            Some(decimal) => decimal,
+            None => return,
+        };
+
+        if check_f32 && !decimal.is_exactly_representable(FloatFormat::F32) {
+            let message = inexact_message(lit, &decimal, FloatFormat::F32);
+            if parameters.deny_inexact_f32 {
+                self.errors.push(syn::Error::new(lit.span(), message));
+            } else {
+                self.warnings.push(Warning::new(lit.span(), message));
+            }
+            return;
+        }
+
+        // Unless the literal is explicitly an `f32`, it is parsed as an `f64` before replacement
+        let format = match lit {
+            Lit::Float(float_lit) if float_lit.suffix() == "f32" => FloatFormat::F32,
+            Lit::Int(int_lit) if int_lit.suffix() == "f32" => FloatFormat::F32,
+            _ => FloatFormat::F64,
+        };
+        if parameters.warn_inexact && !decimal.is_exactly_representable(format) {
+            let message = format!(
+                "{}. Consider an exact placeholder such as `literal_scaled` instead",
+                inexact_message(lit, &decimal, format)
+            );
+            self.warnings.push(Warning::new(lit.span(), message));
+        }
+    }
+
+    /// Checks that the value of the (possibly negated) integer literal is within the allowed range.
+    fn check_range(&mut self, expr: &Expr) {
+        let parameters = self.parameters;
+        // Literals given an integer suffix must fit in that type
+        let fits = parameters.fits.or(match self.int_suffix {
+            Some(PrimitiveSuffix::Int(integer_type)) => Some(integer_type),
+            _ => None,
+        });
+        if fits.is_none() && parameters.min.is_none() && parameters.max.is_none() {
+            return;
+        }
+        let (lit_expr, negated) = match (negated_int_literal(expr), expr) {
+            (Some(lit_expr), _) => (lit_expr, true),
+            (None, Expr::Lit(lit_expr)) => (lit_expr, false),
+            _ => return,
+        };
+        let magnitude = match &lit_expr.lit {
+            Lit::Int(lit) => match lit.base10_parse::<u128>() {
+                Ok(magnitude) => magnitude,
+                Err(_) => return,
+            },
+            _ => return,
+        };
+
+        let value = IntegerValue::new(negated, magnitude);
+        let message = if let Some(fits) =
+            fits.filter(|t| !value.at_least(t.min) || (!value.negative && value.magnitude > t.max))
+        {
+            format!(
+                "integer literal `{}` does not fit in `{}`",
+                value, fits.name
+            )
+        } else if let Some(min) = parameters.min.filter(|min| !value.at_least(*min)) {
+            format!("integer literal `{}` is less than `min = {}`", value, min)
+        } else if let Some(max) = parameters.max.filter(|max| !value.at_most(*max)) {
+            format!(
+                "integer literal `{}` is greater than `max = {}`",
+                value, max
+            )
+        } else {
+            return;
+        };
+        self.errors.push(syn::Error::new_spanned(expr, message));
+    }
+
+    fn replace(&mut self, expr: &mut Expr, replacement: &Replacement<Template>) {
+        if let Expr::Lit(lit_expr) = expr {
+            if self.in_foreign_item {
+                self.errors.push(syn::Error::new(
+                    lit_expr.lit.span(),
+                    "numeric literals cannot be replaced in an `extern` block, since foreign items \
+                     have no bodies and literals can only appear in their types",
+                ));
+                return;
+            }
+            if !check_suffix(&lit_expr.lit, self.parameters, &mut self.errors) {
+                return;
+            }
+            if self.denies_index_replacement(&lit_expr.lit) {
+                return;
+            }
+            if let PrimitiveClass::Float = determine_primitive_class(lit_expr) {
+                self.check_exactness(lit_expr);
+            }
+            let suffix = match determine_primitive_class(lit_expr) {
+                PrimitiveClass::Float => self.float_suffix,
+                PrimitiveClass::Int => self.int_suffix,
+                PrimitiveClass::Other => None,
+            };
+            let (index, item_name, parameters) =
+                (self.current_index, self.item_name, self.parameters);
+            let instantiate =
+                || instantiate_replacement(replacement, lit_expr, index, item_name, parameters);
+            let instantiated = match (suffix, &mut self.hoisted) {
+                (Some(suffix), _) => suffix.apply(&lit_expr.lit).map(|lit| {
+                    marked(Expr::Lit(ExprLit {
+                        attrs: Vec::new(),
+                        lit,
+                    }))
+                }),
+                (None, Some(hoisted)) => hoisted.reference(lit_expr, instantiate).map(marked),
+                (None, None) => instantiate(),
+            };
+            match instantiated {
+                Ok(replacement) => {
+                    let replacement = if self.parameters.expansion_markers {
+                        with_expansion_marker(replacement)
+                    } else {
+                        replacement
+                    };
+                    if self.parameters.dry_run {
+                        self.warnings.push(dry_run_warning(lit_expr, &replacement));
+                    } else if self.parameters.trace {
+                        self.warnings.push(trace_warning(lit_expr));
+                    }
+                    // Replacements are only reported if they are actually made
+                    #[cfg(feature = "debug-output")]
+                    if !self.parameters.dry_run && debug_output::is_enabled() {
+                        debug_output::report(lit_expr, kind_name(lit_expr), unmarked(&replacement));
+                    }
+                    *expr = replacement;
+                }
+                Err(err) => self.errors.push(err),
+            }
+            self.current_index += 1;
+        }
+    }
+
+    /// Reports the literal if it is in an index and `deny_index_replacement = true`.
+    fn denies_index_replacement(&mut self, lit: &Lit) -> bool {
+        if !self.in_index || !self.parameters.deny_index_replacement {
+            return false;
+        }
+        self.errors.push(syn::Error::new(
+            lit.span(),
+            "literal in an index would be replaced, which is denied by \
+             `deny_index_replacement = true`. Consider a suffixed literal such as `0usize` \
+             with `ignore_suffixed = true`, `skip_ranges = true` for range endpoints, or \
+             restricting the replaced literals with `skip_in_calls` or `only_in_bindings`",
+        ));
+        true
+    }
+
+    /// Enters the arguments of a call to the callable with the given path, and returns the state
+    /// of the enclosing expression, which is restored by `exit_call`.
+    fn enter_call(&mut self, path: &[String]) -> (bool, bool) {
+        let outer = (self.in_listed_call, self.in_skipped_call);
+        self.in_listed_call |= lists_call(&self.parameters.only_in_calls, path);
+        self.in_skipped_call |= lists_call(&self.parameters.skip_in_calls, path);
+        outer
+    }
+
+    fn exit_call(&mut self, (in_listed_call, in_skipped_call): (bool, bool)) {
+        self.in_listed_call = in_listed_call;
+        self.in_skipped_call = in_skipped_call;
+    }
+
+    /// Starts hoisting the literals of a function body with `hoist = true`, and returns the
+    /// hoisted literals of the enclosing function body.
+    ///
+    /// Literals in `const fn`s are not hoisted, since cloning is not allowed in const contexts.
+    fn enter_fn(&mut self, sig: &Signature) -> Option<Hoisted> {
+        let hoisted = if self.parameters.hoist && sig.constness.is_none() {
+            Some(Hoisted::new())
+        } else {
+            None
+        };
+        std::mem::replace(&mut self.hoisted, hoisted)
+    }
+
+    /// Inserts the bindings of the hoisted literals into the function body, and resumes hoisting
+    /// the literals of the enclosing function body.
+    fn exit_fn(&mut self, outer: Option<Hoisted>, block: Option<&mut Block>) {
+        let hoisted = std::mem::replace(&mut self.hoisted, outer);
+        if let (Some(hoisted), Some(block)) = (hoisted, block) {
+            hoisted.insert_bindings(block);
+        }
+    }
+
+    /// Visits an expression with `context = arithmetic`, in which literals are only replaced if
+    /// `operand` is true.
+    ///
+    /// Literals are operands if they are an operand of `+`, `-`, `*`, `/`, `%` (or the
+    /// corresponding compound assignment) or of a comparison, and the other operand is not a
+    /// literal, or if they are an argument of a method call on a receiver that is not a literal.
+    /// Parentheses and negation do not change whether a literal is an operand.
+    fn visit_operand_mut(&mut self, expr: &mut Expr, operand: bool) {
+        self.operand = operand;
+        self.visit_expr_mut(expr);
+    }
+
+    /// Visits a position that cannot refer to the bindings of hoisted literals, such as a closure
+    /// that may be `move` or an array length, in which literals are replaced in place.
+    fn without_hoisting<F: FnOnce(&mut Self)>(&mut self, visit: F) {
+        let outer = self.hoisted.take();
+        visit(self);
+        self.hoisted = outer;
+    }
+
+    /// Visits a position that requires a constant such as an array length or a const generic
+    /// argument, in which literals are replaced in place and integer literals are not promoted.
+    fn within_length<F: FnOnce(&mut Self)>(&mut self, visit: F) {
+        let outer = std::mem::replace(&mut self.in_length, true);
+        self.without_hoisting(visit);
+        self.in_length = outer;
+    }
+
+    /// Visits an expression that is kept as verbatim tokens, since its syntax is not supported by
+    /// the parser. Verbatim expressions that are not recognized fall back to replacing their
+    /// literal tokens.
+    fn visit_verbatim_mut(&mut self, tokens: &mut proc_macro2::TokenStream) {
+        match syn::parse2::<VerbatimExpr>(tokens.clone()) {
+            Ok(mut verbatim) => {
+                match &mut verbatim {
+                    VerbatimExpr::LetElse {
+                        pattern,
+                        expr,
+                        block,
+                        ..
+                    } => {
+                        let outer = self.in_listed_binding;
+                        self.in_listed_binding |= matches!(syn::parse2::<Pat>(pattern.clone()), Ok(pat) if self.parameters.lists_binding(&pat));
+                        self.visit_expr_mut(expr);
+                        self.in_listed_binding = outer;
+                        self.visit_block_mut(block);
+                    }
+                    // Bindings at the start of the function body cannot be used in const contexts
+                    VerbatimExpr::ConstBlock { block, .. } => {
+                        if self.parameters.visit_inline_const {
+                            self.without_hoisting(|visitor| visitor.visit_block_mut(block))
+                        }
+                    }
+                }
+                *tokens = verbatim.into_token_stream();
+            }
+            Err(_) => *tokens = visit_literal_tokens_mut(self, std::mem::take(tokens)),
+        }
+    }
+}
+
+impl<'a> CountsReplacements for LiteralVisitor<'a> {
+    fn replacement_count(&self) -> usize {
+        self.current_index
+    }
+}
+
+impl<'a> VisitMut for LiteralVisitor<'a> {
+    fn visit_item_mut(&mut self, item: &mut Item) {
+        if !has_replacement_attribute(item_attrs(item), self.attribute_aliases) {
+            self.without_hoisting(|visitor| visit_item_mut(visitor, item));
+        }
+    }
+
+    fn visit_item_fn_mut(&mut self, item: &mut ItemFn) {
+        let outer = self.enter_fn(&item.sig);
+        visit_item_fn_mut(self, item);
+        self.exit_fn(outer, Some(&mut item.block));
+    }
+
+    fn visit_impl_item_method_mut(&mut self, item: &mut ImplItemMethod) {
+        if !has_replacement_attribute(&item.attrs, self.attribute_aliases) {
+            let outer = self.enter_fn(&item.sig);
+            visit_impl_item_method_mut(self, item);
+            self.exit_fn(outer, Some(&mut item.block));
+        }
+    }
+
+    /// Foreign items only consist of declarations, so any literal that would be replaced in them
+    /// is reported as an error.
+    fn visit_item_foreign_mod_mut(&mut self, item: &mut ItemForeignMod) {
+        let outer = std::mem::replace(&mut self.in_foreign_item, true);
+        visit_item_foreign_mod_mut(self, item);
+        self.in_foreign_item = outer;
+    }
+
+    /// Only the items of a trait are visited, since its generics and supertraits are part of its
+    /// interface.
+    fn visit_item_trait_mut(&mut self, item: &mut ItemTrait) {
+        for trait_item in &mut item.items {
+            self.visit_trait_item_mut(trait_item);
+        }
+    }
+
+    /// Only the default body of a method of a trait is visited, and not its signature.
+    fn visit_trait_item_method_mut(&mut self, item: &mut TraitItemMethod) {
+        if has_replacement_attribute(&item.attrs, self.attribute_aliases) {
+            return;
+        }
+        if let Some(block) = &mut item.default {
+            let outer = self.enter_fn(&item.sig);
+            self.visit_block_mut(block);
+            self.exit_fn(outer, Some(block));
+        }
+    }
+
+    /// Only the default value of a constant of a trait is visited, and not its type.
+    fn visit_trait_item_const_mut(&mut self, item: &mut TraitItemConst) {
+        if let Some((_, default)) = &mut item.default {
+            self.visit_expr_mut(default);
+        }
+    }
+
+    /// Associated types of traits consist of bounds and a default type, which are left untouched.
+    fn visit_trait_item_type_mut(&mut self, _item: &mut TraitItemType) {}
+
+    fn visit_expr_closure_mut(&mut self, closure: &mut ExprClosure) {
+        self.without_hoisting(|visitor| visit_expr_closure_mut(visitor, closure));
+    }
+
+    fn visit_expr_async_mut(&mut self, block: &mut ExprAsync) {
+        self.without_hoisting(|visitor| visit_expr_async_mut(visitor, block));
+    }
+
+    fn visit_expr_unsafe_mut(&mut self, block: &mut ExprUnsafe) {
+        if self.parameters.visit_unsafe {
+            visit_expr_unsafe_mut(self, block);
+        }
+    }
+
+    fn visit_expr_repeat_mut(&mut self, repeat: &mut ExprRepeat) {
+        self.visit_expr_mut(&mut repeat.expr);
+        self.within_length(|visitor| visitor.visit_expr_mut(&mut repeat.len));
+    }
+
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        self.within_length(|visitor| visit_type_mut(visitor, ty));
+    }
+
+    fn visit_generic_argument_mut(&mut self, argument: &mut GenericArgument) {
+        if let GenericArgument::Const(_) = argument {
+            if !self.parameters.visit_generic_args {
+                return;
+            }
+        }
+        self.within_length(|visitor| visit_generic_argument_mut(visitor, argument));
+        if let GenericArgument::Const(expr) = argument {
+            braced_if_replaced(expr);
+        }
+    }
+
+    fn visit_method_turbofish_mut(&mut self, turbofish: &mut MethodTurbofish) {
+        // Const generic arguments of methods, as in `fixed_rows::<3>()`, are left untouched
+        for argument in &mut turbofish.args {
+            if let GenericMethodArgument::Type(ty) = argument {
+                self.visit_type_mut(ty);
+            }
+        }
+    }
+
+    fn visit_expr_path_mut(&mut self, path_expr: &mut ExprPath) {
+        if let Some(qself) = &mut path_expr.qself {
+            self.visit_type_mut(&mut qself.ty);
+        }
+        // Const generic arguments of the function itself, as in `from_fn::<_, 4, _>`, are left
+        // untouched, while those of types, as in `SMatrix::<T, 4, 4>::zeros`, are visited
+        let last = path_expr.path.segments.len() - 1;
+        for (index, segment) in path_expr.path.segments.iter_mut().enumerate() {
+            if let PathArguments::AngleBracketed(arguments) = &mut segment.arguments {
+                for argument in &mut arguments.args {
+                    if index < last || !matches!(argument, GenericArgument::Const(_)) {
+                        self.visit_generic_argument_mut(argument);
+                    }
+                }
+            }
+        }
+    }
+
+    fn visit_expr_binary_mut(&mut self, binary: &mut ExprBinary) {
+        if self.parameters.skip_shift_rhs && is_shift(&binary.op) {
+            self.visit_expr_mut(&mut binary.left);
+        } else if self.parameters.context == LiteralContext::Arithmetic && is_arithmetic(&binary.op)
+        {
+            let (left_is_literal, right_is_literal) =
+                (is_literal(&binary.left), is_literal(&binary.right));
+            self.visit_operand_mut(&mut binary.left, !right_is_literal);
+            self.visit_operand_mut(&mut binary.right, !left_is_literal);
+        } else {
+            visit_expr_binary_mut(self, binary);
+        }
+    }
+
+    fn visit_expr_assign_op_mut(&mut self, assign: &mut ExprAssignOp) {
+        if self.parameters.skip_shift_rhs && is_shift(&assign.op) {
+            self.visit_expr_mut(&mut assign.left);
+        } else if self.parameters.context == LiteralContext::Arithmetic && is_arithmetic(&assign.op)
+        {
+            self.visit_expr_mut(&mut assign.left);
+            let left_is_literal = is_literal(&assign.left);
+            self.visit_operand_mut(&mut assign.right, !left_is_literal);
+        } else {
+            visit_expr_assign_op_mut(self, assign);
+        }
+    }
+
+    fn visit_local_mut(&mut self, local: &mut Local) {
+        let outer = self.in_listed_binding;
+        self.in_listed_binding |= self.parameters.lists_binding(&local.pat);
+        visit_local_mut(self, local);
+        self.in_listed_binding = outer;
+    }
+
+    fn visit_expr_call_mut(&mut self, call: &mut ExprCall) {
+        self.visit_expr_mut(&mut call.func);
+        let path = match &*call.func {
+            Expr::Path(path_expr) => path_segments(&path_expr.path),
+            _ => Vec::new(),
+        };
+        let outer = self.enter_call(&path);
+        for argument in &mut call.args {
+            self.visit_expr_mut(argument);
+        }
+        self.exit_call(outer);
+    }
+
+    fn visit_expr_method_call_mut(&mut self, call: &mut ExprMethodCall) {
+        let receiver_is_literal = is_literal(&call.receiver);
+        self.visit_expr_mut(&mut call.receiver);
+        if let Some(turbofish) = &mut call.turbofish {
+            self.visit_method_turbofish_mut(turbofish);
+        }
+        let outer = self.enter_call(&[call.method.to_string()]);
+        for argument in &mut call.args {
+            if self.parameters.context == LiteralContext::Arithmetic {
+                self.visit_operand_mut(argument, !receiver_is_literal);
+            } else {
+                self.visit_expr_mut(argument);
+            }
+        }
+        self.exit_call(outer);
+    }
+
+    fn visit_expr_index_mut(&mut self, index: &mut ExprIndex) {
+        self.visit_expr_mut(&mut index.expr);
+        let outer = std::mem::replace(&mut self.in_index, true);
+        self.visit_expr_mut(&mut index.index);
+        self.in_index = outer;
+    }
+
+    fn visit_expr_range_mut(&mut self, range: &mut ExprRange) {
+        if !self.parameters.skip_ranges {
+            return visit_expr_range_mut(self, range);
+        }
+        // Endpoints that are literals themselves are left untouched, as in `0..n` and `2..8`
+        for endpoint in range.from.iter_mut().chain(range.to.iter_mut()) {
+            if !matches!(**endpoint, Expr::Lit(_)) && negated_int_literal(endpoint).is_none() {
+                self.visit_expr_mut(endpoint);
+            }
+        }
+    }
+
+    fn visit_where_clause_mut(&mut self, where_clause: &mut WhereClause) {
+        if self.parameters.visit_where_literals {
+            self.without_hoisting(|visitor| visit_where_clause_mut(visitor, where_clause));
+        }
+    }
+
+    fn visit_const_param_mut(&mut self, param: &mut ConstParam) {
+        self.within_length(|visitor| visit_const_param_mut(visitor, param));
+        if let Some(default) = &mut param.default {
+            braced_if_replaced(default);
+        }
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if is_marked(expr) {
+            return;
+        }
+        // The literals of a folded expression are operands, and so is the literal they fold to
+        if self.fold_constants(expr) {
+            self.operand = true;
+        }
+        let operand = std::mem::replace(&mut self.operand, false);
+        if self.parameters.context == LiteralContext::Arithmetic {
+            if negated_int_literal(expr).is_some() {
+                if !operand {
+                    return;
+                }
+            } else {
+                match expr {
+                    // The operand is the expression in parentheses or the negated expression
+                    Expr::Paren(ExprParen { expr: inner, .. })
+                    | Expr::Group(ExprGroup { expr: inner, .. })
+                    | Expr::Unary(ExprUnary {
+                        op: UnOp::Neg(_),
+                        expr: inner,
+                        ..
+                    }) if operand => return self.visit_operand_mut(inner, true),
+                    Expr::Lit(_) if !operand => return,
+                    _ => {}
+                }
+            }
+        }
+        if (self.in_skipped_call
+            || !self.parameters.only_in_calls.is_empty() && !self.in_listed_call
+            || !self.parameters.only_in_bindings.is_empty() && !self.in_listed_binding)
+            && (matches!(expr, Expr::Lit(_)) || negated_int_literal(expr).is_some())
+        {
+            return;
+        }
+        // Negated integer literals are range checked as a whole
+        if let (Some(lit_expr), Some(replacement)) =
+            (negated_int_literal(expr), self.int_replacement)
+        {
+            if self.values.includes(&lit_expr.lit) {
+                self.check_range(expr);
+                if let Expr::Unary(unary) = expr {
+                    self.replace(&mut unary.expr, replacement);
+                }
+            }
+            return;
+        }
+        if let Expr::Verbatim(tokens) = expr {
+            self.visit_verbatim_mut(tokens);
+            return;
+        }
+        if let Expr::Lit(lit_expr) = expr {
+            if self.promotes_ints() {
+                // The literal is reported as written, rather than as the promoted float literal
+                let unsuffixed_int =
+                    matches!(&lit_expr.lit, Lit::Int(lit) if lit.suffix().is_empty());
+                if unsuffixed_int && self.denies_index_replacement(&lit_expr.lit) {
+                    return;
+                }
+                promote_int(lit_expr);
+            }
+            let class = determine_primitive_class(lit_expr);
+            let replacement = match class {
+                PrimitiveClass::Float => self.float_replacement,
+                PrimitiveClass::Int => self.int_replacement,
+                PrimitiveClass::Other => None,
+            };
+            if let Some(replacement) = replacement {
+                if self.values.includes(&lit_expr.lit) {
+                    if let PrimitiveClass::Int = class {
+                        self.check_range(expr);
+                    }
+                    self.replace(expr, replacement);
+                }
+            }
+            return;
+        }
+        visit_expr_mut(self, expr)
+    }
+
+    fn visit_macro_mut(&mut self, mac: &mut Macro) {
+        if is_asm_macro(mac) || is_offset_of_macro(mac) {
+            return;
+        }
+        let parameters = self.parameters;
+        let fallback = parameters.macro_fallback;
+        if parameters.visit_macros && !visit_macros_mut(self, mac, parameters) {
+            let replaces_float = self.replaces(PrimitiveClass::Float);
+            let replaces_int = self.replaces(PrimitiveClass::Int);
+            report_skipped_macro(
+                mac,
+                fallback,
+                parameters.strict,
+                |class| match class {
+                    PrimitiveClass::Float => replaces_float,
+                    PrimitiveClass::Int => replaces_int,
+                    PrimitiveClass::Other => false,
+                },
+                &mut self.errors,
+                &mut self.warnings,
+            );
+        }
+    }
+}
+
+/// Finds `const fn`s and constants containing literals that will be replaced, and warns about
+/// them if the replacement expression may not be const-evaluable.
+struct ConstFnVisitor<'a, F> {
+    pub is_replaced: F,
+    pub attribute_aliases: &'a [String],
+    pub warnings: Vec<Warning>,
+}
+
+impl<'a, F: Fn(PrimitiveClass) -> bool> ConstFnVisitor<'a, F> {
+    fn check(&mut self, sig: &Signature, block: &Block) {
+        if let Some(constness) = &sig.constness {
+            let mut literals = self.literal_finder();
+            literals.visit_block(block);
+            if literals.found {
+                self.warn(constness.span, format!("const fn {}", sig.ident));
+            }
+        }
+    }
+
+    /// Checks the value of a constant, including associated constants in `impl` blocks and traits.
+    fn check_const(&mut self, const_token: &Token![const], ident: &Ident, expr: &Expr) {
+        let mut literals = self.literal_finder();
+        literals.visit_expr(expr);
+        if literals.found {
+            self.warn(const_token.span, format!("const {}", ident));
+        }
+    }
+
+    fn literal_finder(&self) -> LiteralFinder<'_, F> {
+        LiteralFinder {
+            is_replaced: &self.is_replaced,
+            found: false,
+        }
+    }
+
+    fn warn(&mut self, span: Span, context: String) {
+        self.warnings.push(Warning::new(
+            span,
+            format!(
+                "the replacement expression may not be const-evaluable in `{}`. \
+                 Add `const_safe = true` to the attribute if it is",
+                context
+            ),
+        ));
+    }
+}
+
+impl<'a, 'ast, F: Fn(PrimitiveClass) -> bool> Visit<'ast> for ConstFnVisitor<'a, F> {
+    fn visit_item(&mut self, item: &'ast Item) {
+        if !has_replacement_attribute(item_attrs(item), self.attribute_aliases) {
+            syn::visit::visit_item(self, item);
+        }
+    }
+
+    fn visit_item_fn(&mut self, item: &'ast ItemFn) {
+        self.check(&item.sig, &item.block);
+        syn::visit::visit_item_fn(self, item);
+    }
+
+    fn visit_item_const(&mut self, item: &'ast ItemConst) {
+        self.check_const(&item.const_token, &item.ident, &item.expr);
+        syn::visit::visit_item_const(self, item);
+    }
+
+    fn visit_impl_item_method(&mut self, item: &'ast ImplItemMethod) {
+        if !has_replacement_attribute(&item.attrs, self.attribute_aliases) {
+            self.check(&item.sig, &item.block);
+            syn::visit::visit_impl_item_method(self, item);
+        }
+    }
+
+    fn visit_impl_item_const(&mut self, item: &'ast ImplItemConst) {
+        self.check_const(&item.const_token, &item.ident, &item.expr);
+        syn::visit::visit_impl_item_const(self, item);
+    }
+
+    fn visit_trait_item_method(&mut self, item: &'ast TraitItemMethod) {
+        if has_replacement_attribute(&item.attrs, self.attribute_aliases) {
+            return;
+        }
+        if let Some(block) = &item.default {
+            self.check(&item.sig, block);
+        }
+        syn::visit::visit_trait_item_method(self, item);
+    }
+
+    fn visit_trait_item_const(&mut self, item: &'ast TraitItemConst) {
+        if let Some((_, expr)) = &item.default {
+            self.check_const(&item.const_token, &item.ident, expr);
+        }
+        syn::visit::visit_trait_item_const(self, item);
+    }
+}
+
+/// Determines whether a block contains any literal that will be replaced.
+struct LiteralFinder<'a, F> {
+    pub is_replaced: &'a F,
+    pub found: bool,
+}
+
+impl<'a, 'ast, F: Fn(PrimitiveClass) -> bool> Visit<'ast> for LiteralFinder<'a, F> {
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        if !is_marked(expr) {
+            syn::visit::visit_expr(self, expr);
+        }
+    }
+
+    fn visit_expr_lit(&mut self, lit_expr: &'ast ExprLit) {
+        if (self.is_replaced)(determine_primitive_class(lit_expr)) {
+            self.found = true;
+        }
+    }
+}
+
+/// Determines whether an expression contains calls, which may not be allowed in const contexts.
+struct CallFinder {
+    pub found: bool,
+}
+
+impl<'ast> Visit<'ast> for CallFinder {
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        match expr {
+            Expr::Call(_) | Expr::MethodCall(_) | Expr::Macro(_) => self.found = true,
+            _ => syn::visit::visit_expr(self, expr),
+        }
+    }
+}
+
+/// Determines whether an item contains any expression or macro invocation, and hence any position
+/// in which a literal could be replaced.
+struct ExpressionFinder {
+    pub found: bool,
+}
+
+impl<'ast> Visit<'ast> for ExpressionFinder {
+    fn visit_expr(&mut self, _expr: &'ast Expr) {
+        self.found = true;
+    }
+
+    fn visit_macro(&mut self, _mac: &'ast Macro) {
+        self.found = true;
     }
 
-    // Handle , punctuation based macros (e.g. vec with list, assert_eq)
-    let parser_comma = Punctuated::<Expr, Token![,]>::parse_terminated;
-    if try_parse_punctuated_macro(visitor, mac, parser_comma) {
-        return;
+    fn visit_item(&mut self, item: &'ast Item) {
+        match item {
+            Item::Verbatim(_) => self.found = true,
+            _ => syn::visit::visit_item(self, item),
+        }
     }
+}
 
-    // Handle ; punctuation based macros (e.g. vec with repeat)
-    let parser_semicolon = Punctuated::<Expr, Token![;]>::parse_terminated;
-    if try_parse_punctuated_macro(visitor, mac, parser_semicolon) {
+/// Reports the attribute if the item contains no expressions, such as a unit struct, a type alias
+/// or an empty trait, in which case it usually has been placed on the wrong item.
+fn check_has_expressions(
+    parameters: &MacroParameters,
+    item: &Item,
+    errors: &mut Vec<syn::Error>,
+    warnings: &mut Vec<Warning>,
+) {
+    let mut finder = ExpressionFinder { found: false };
+    finder.visit_item(item);
+    if finder.found {
         return;
     }
+    let message = "the attribute has no effect, since the item contains no expressions";
+    if parameters.strict {
+        errors.push(syn::Error::new(Span::call_site(), message));
+    } else {
+        warnings.push(Warning::new(Span::call_site(), message));
+    }
 }
 
-impl<'a> VisitMut for FloatLiteralVisitor<'a> {
-    fn visit_expr_mut(&mut self, expr: &mut Expr) {
-        if let Expr::Lit(lit_expr) = expr {
-            if let PrimitiveClass::Float = determine_primitive_class(&lit_expr) {
-                let mut adapted_replacement = self.replacement.clone();
-                replace_literal(&mut adapted_replacement, self.placeholder, lit_expr);
-                *expr = adapted_replacement;
-                return;
-            }
-        }
-        visit_expr_mut(self, expr)
+/// Collects the identifiers in a replacement expression that refer to variables in the scope of
+/// the replaced literals, such as `x` in `x + literal as f64`.
+///
+/// Only names that start with a lowercase letter or an underscore are considered variables, so
+/// that constants such as `N` are not collected. Placeholders, names bound in the expression
+/// itself (e.g. by a closure) and called functions are not free either.
+struct FreeIdentifierFinder {
+    bound: Vec<String>,
+    free: Vec<Ident>,
+}
+
+impl<'ast> Visit<'ast> for FreeIdentifierFinder {
+    fn visit_pat_ident(&mut self, pat_ident: &'ast PatIdent) {
+        self.bound.push(pat_ident.ident.to_string());
+        syn::visit::visit_pat_ident(self, pat_ident);
     }
 
-    fn visit_macro_mut(&mut self, mac: &mut Macro) {
-        if self.parameters.visit_macros {
-            visit_macros_mut(self, mac);
+    fn visit_expr_call(&mut self, call: &'ast ExprCall) {
+        if !matches!(&*call.func, Expr::Path(_)) {
+            self.visit_expr(&call.func);
+        }
+        for argument in &call.args {
+            self.visit_expr(argument);
         }
     }
-}
 
-impl<'a> VisitMut for IntLiteralVisitor<'a> {
-    fn visit_expr_mut(&mut self, expr: &mut Expr) {
-        if let Expr::Lit(lit_expr) = expr {
-            if let PrimitiveClass::Int = determine_primitive_class(&lit_expr) {
-                let mut adapted_replacement = self.replacement.clone();
-                replace_literal(&mut adapted_replacement, self.placeholder, lit_expr);
-                *expr = adapted_replacement;
-                return;
+    fn visit_expr_path(&mut self, path_expr: &'ast ExprPath) {
+        if let (None, Some(ident)) = (&path_expr.qself, path_expr.path.get_ident()) {
+            let name = ident.to_string();
+            let is_variable = name.starts_with(|c: char| c.is_lowercase() || c == '_');
+            if is_variable && name != "self" && Placeholder::from_name(&name, "literal").is_none() {
+                self.free.push(ident.clone());
             }
         }
-        visit_expr_mut(self, expr)
+        syn::visit::visit_expr_path(self, path_expr);
     }
+}
 
-    fn visit_macro_mut(&mut self, mac: &mut Macro) {
-        if self.parameters.visit_macros {
-            visit_macros_mut(self, mac);
+/// Warns about variables of the surrounding scope that the replacement expression refers to,
+/// unless they are listed in `captures`.
+///
+/// The replacement is inserted in place of every literal, so such a name silently refers to
+/// whichever variable of that name is in scope at the literal.
+fn check_free_identifiers(
+    replacements: &Replacements,
+    parameters: &MacroParameters,
+    warnings: &mut Vec<Warning>,
+) {
+    let mut finder = FreeIdentifierFinder {
+        bound: Vec::new(),
+        free: Vec::new(),
+    };
+    for replacement in [&replacements.float, &replacements.int].iter() {
+        // The names of primitive types are attached to the literals as suffixes
+        if PrimitiveSuffix::from_replacement(replacement).is_some() {
+            continue;
+        }
+        for expr in replacement.expressions() {
+            finder.visit_expr(expr);
         }
     }
-}
-
-impl<'a> VisitMut for NumericLiteralVisitor<'a> {
-    fn visit_expr_mut(&mut self, expr: &mut Expr) {
-        if let Expr::Lit(lit_expr) = expr {
-            // TODO: Currently we cannot correctly treat integers that don't fit in 64
-            //  bits. For this we'd have to deal with verbatim literals and manually
-            //  parse the string
-
-            match determine_primitive_class(&lit_expr) {
-                PrimitiveClass::Float => {
-                    let mut visitor = FloatLiteralVisitor {
-                        parameters: self.parameters,
-                        placeholder: self.placeholder,
-                        replacement: self.float_replacement,
-                    };
-                    visitor.visit_expr_mut(expr);
-                    return;
-                }
-                PrimitiveClass::Int => {
-                    let mut visitor = IntLiteralVisitor {
-                        parameters: self.parameters,
-                        placeholder: self.placeholder,
-                        replacement: self.int_replacement,
-                    };
-                    visitor.visit_expr_mut(expr);
-                    return;
-                }
-                _ => {}
-            }
+    let mut reported = Vec::new();
+    for ident in finder.free {
+        let name = ident.to_string();
+        if finder.bound.contains(&name)
+            || parameters.captures.contains(&name)
+            || reported.contains(&name)
+        {
+            continue;
         }
-        visit_expr_mut(self, expr)
+        warnings.push(Warning::new(
+            ident.span(),
+            format!(
+                "the replacement refers to `{}` in the scope of each replaced literal, \
+                 add `captures = [{}]` if this is intended",
+                name, name
+            ),
+        ));
+        reported.push(name);
     }
+}
 
-    fn visit_macro_mut(&mut self, mac: &mut Macro) {
-        if self.parameters.visit_macros {
-            visit_macros_mut(self, mac);
+/// Warns about `const fn`s in the item in which the replacement expression may not be const-evaluable.
+fn const_fn_warnings<F: Fn(PrimitiveClass) -> bool>(
+    item: &Item,
+    replacements: &Replacements,
+    parameters: &MacroParameters,
+    attribute_aliases: &[String],
+    is_replaced: F,
+) -> Vec<Warning> {
+    let mut calls = CallFinder { found: false };
+    for replacement in [&replacements.float, &replacements.int].iter() {
+        for expr in replacement.expressions() {
+            calls.visit_expr(expr);
         }
     }
+    if parameters.const_safe || !calls.found {
+        return Vec::new();
+    }
+
+    let mut visitor = ConstFnVisitor {
+        is_replaced,
+        attribute_aliases,
+        warnings: Vec::new(),
+    };
+    // The decorated item itself may carry further attributes of this crate, so it is always visited
+    syn::visit::visit_item(&mut visitor, item);
+    visitor.warnings
 }
 
-/// Visits the "replacement expression", which replaces a placeholder identifier
-/// with the given literal.
-struct ReplacementExpressionVisitor<'a> {
-    pub placeholder: &'a str,
+/// Computes the values that the placeholders of the replacement expression stand for.
+struct LiteralSubstitution<'a> {
     pub literal: &'a ExprLit,
+    /// Index of the literal among the replaced literals of the decorated item
+    pub index: usize,
+    /// Name of the decorated item
+    pub item_name: &'a str,
+    pub parameters: &'a MacroParameters,
 }
 
-impl<'a> VisitMut for ReplacementExpressionVisitor<'a> {
-    fn visit_expr_mut(&mut self, expr: &mut Expr) {
-        if let Expr::Path(path_expr) = expr {
-            if let Some(last_segment) = path_expr.path.segments.last() {
-                if last_segment.ident == self.placeholder {
-                    *expr = Expr::Lit(self.literal.clone());
-                    return;
-                }
-            }
+impl<'a> LiteralSubstitution<'a> {
+    /// Returns the expression to substitute for the placeholder.
+    fn substitute(&self, placeholder: Placeholder) -> Result<Expr, syn::Error> {
+        match placeholder {
+            Placeholder::Literal => Ok(Expr::Lit(self.literal.clone())),
+            Placeholder::Scaled => self.scaled_literal(),
+            Placeholder::Kind => Ok(self.kind_literal()),
+            Placeholder::Index => Ok(self.index_literal()),
+            Placeholder::Text => Ok(self.text_literal()),
+            Placeholder::Float => self.float_literal(),
+            Placeholder::File => Ok(self.file_literal()),
+            Placeholder::Line => Ok(self.line_literal()),
+            Placeholder::Column => Ok(self.column_literal()),
         }
-        visit_expr_mut(self, expr)
+    }
+
+    /// Returns whether the compiler provides the locations of spans to procedural macros, which
+    /// requires Rust 1.88 or later.
+    fn has_location(&self) -> bool {
+        self.literal.lit.span().start().line != 0
+    }
+
+    /// The source file of the literal as a string literal, e.g. `"src/lib.rs"`, or the name of the
+    /// decorated item without span locations.
+    fn file_literal(&self) -> Expr {
+        let file = if self.has_location() {
+            self.literal.lit.span().file()
+        } else {
+            self.item_name.to_string()
+        };
+        Expr::Lit(ExprLit {
+            attrs: Vec::new(),
+            lit: Lit::Str(LitStr::new(&file, synthesized_span(&self.literal.lit))),
+        })
+    }
+
+    /// The 1-based line of the literal, or the 1-based index of the literal among the replaced
+    /// literals of the decorated item without span locations.
+    fn line_literal(&self) -> Expr {
+        let line = if self.has_location() {
+            self.literal.lit.span().start().line
+        } else {
+            self.index + 1
+        };
+        self.location_literal(line)
+    }
+
+    /// The 1-based column of the literal, like `column!()`, or `0` without span locations.
+    fn column_literal(&self) -> Expr {
+        let column = if self.has_location() {
+            self.literal.lit.span().start().column + 1
+        } else {
+            0
+        };
+        self.location_literal(column)
+    }
+
+    fn location_literal(&self, value: usize) -> Expr {
+        Expr::Lit(ExprLit {
+            attrs: Vec::new(),
+            lit: Lit::Int(LitInt::new(
+                &value.to_string(),
+                synthesized_span(&self.literal.lit),
+            )),
+        })
+    }
+
+    /// The literal as a float literal, e.g. `3.0` for `3`. Float literals are kept as they are,
+    /// while integer literals must be exactly representable as `f64`.
+    fn float_literal(&self) -> Result<Expr, syn::Error> {
+        let lit = match &self.literal.lit {
+            Lit::Int(lit) => lit,
+            _ => return Ok(Expr::Lit(self.literal.clone())),
+        };
+        let value = lit.base10_parse::<u128>()?;
+        // An integer is exactly representable if its significant bits fit in the 53-bit mantissa
+        if value != 0 && value >> value.trailing_zeros() >= 1 << f64::MANTISSA_DIGITS {
+            return Err(syn::Error::new(
+                lit.span(),
+                format!(
+                    "integer literal `{}` is not exactly representable as `f64`, so it cannot be \
+                     used for `literal_float`",
+                    lit.base10_digits()
+                ),
+            ));
+        }
+        Ok(Expr::Lit(ExprLit {
+            attrs: Vec::new(),
+            lit: Lit::Float(LitFloat::new(
+                &format!("{}.0", value),
+                synthesized_span(&self.literal.lit),
+            )),
+        }))
+    }
+
+    /// The index of the literal as an unsuffixed integer literal.
+    fn index_literal(&self) -> Expr {
+        Expr::Lit(ExprLit {
+            attrs: Vec::new(),
+            lit: Lit::Int(LitInt::new(
+                &self.index.to_string(),
+                synthesized_span(&self.literal.lit),
+            )),
+        })
+    }
+
+    /// The source text of the literal as a string literal, e.g. `"1.618"`.
+    fn text_literal(&self) -> Expr {
+        let text = self.literal.to_token_stream().to_string();
+        Expr::Lit(ExprLit {
+            attrs: Vec::new(),
+            lit: Lit::Str(LitStr::new(&text, synthesized_span(&self.literal.lit))),
+        })
+    }
+
+    /// The kind of the literal as a string literal, i.e. `"int"` or `"float"`.
+    fn kind_literal(&self) -> Expr {
+        let kind = match determine_primitive_class(self.literal) {
+            PrimitiveClass::Float => "float",
+            PrimitiveClass::Int => "int",
+            PrimitiveClass::Other => "other",
+        };
+        Expr::Lit(ExprLit {
+            attrs: Vec::new(),
+            lit: Lit::Str(LitStr::new(kind, synthesized_span(&self.literal.lit))),
+        })
+    }
+
+    /// The literal multiplied by `10^scale`, computed exactly from its decimal digits.
+    fn scaled_literal(&self) -> Result<Expr, syn::Error> {
+        let lit = &self.literal.lit;
+        let scale = self.parameters.scale;
+        let decimal = Decimal::from_lit(lit).ok_or_else(|| {
+            syn::Error::new(lit.span(), "cannot determine the exact value of literal")
+        })?;
+        let scaled = decimal.scaled(scale).map_err(|err| {
+            let message = match err {
+                ScaleError::TooManyFractionalDigits { fractional_digits } => format!(
+                    "literal has {} fractional digits, but `scale = {}` allows at most {}",
+                    fractional_digits, scale, scale
+                ),
+                ScaleError::Overflow => format!(
+                    "literal multiplied by 10^{} does not fit in a 128-bit integer",
+                    scale
+                ),
+            };
+            syn::Error::new(lit.span(), message)
+        })?;
+        Ok(Expr::Lit(ExprLit {
+            attrs: Vec::new(),
+            lit: Lit::Int(LitInt::new(&scaled.to_string(), synthesized_span(lit))),
+        }))
     }
 }
 
@@ -332,130 +3195,875 @@ impl MacroParameterVisitor {
         visitor.visit_expr(expr);
         let name = visitor.name.take();
         let value = visitor.value.take();
-        name.and_then(|n| value.and_then(|v| Some((n, v))))
+        name.and_then(|n| value.map(|v| (n, v)))
+    }
+}
+
+/// Returns the names of the segments of the path, without their generic arguments.
+fn path_segments(path: &syn::Path) -> Vec<String> {
+    path.segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect()
+}
+
+/// Finds the names bound by a pattern.
+struct BindingFinder<'a> {
+    names: &'a [String],
+    found: bool,
+}
+
+impl<'a, 'ast> Visit<'ast> for BindingFinder<'a> {
+    fn visit_pat_ident(&mut self, pat_ident: &'ast PatIdent) {
+        self.found |= self.names.contains(&pat_ident.ident.to_string());
+        syn::visit::visit_pat_ident(self, pat_ident);
+    }
+}
+
+/// Parses the entries of a list parameter such as `only_in_bindings` as names.
+fn names(entries: Vec<Expr>, name: &str) -> Result<Vec<String>, String> {
+    entries
+        .into_iter()
+        .map(|entry| match entry {
+            Expr::Path(path_expr) if path_expr.qself.is_none() => path_expr
+                .path
+                .get_ident()
+                .map(ToString::to_string)
+                .ok_or_else(|| format!("expected names, e.g. `{} = [alphas, betas]`", name)),
+            _ => Err(format!("expected names, e.g. `{} = [alphas, betas]`", name)),
+        })
+        .collect()
+}
+
+/// Parses the entries of `only_in_calls` or `skip_in_calls` as paths of callables.
+fn call_paths(entries: Vec<Expr>, name: &str) -> Result<Vec<Vec<String>>, String> {
+    entries
+        .into_iter()
+        .map(|entry| match entry {
+            Expr::Path(path_expr) => Ok(path_segments(&path_expr.path)),
+            _ => Err(format!(
+                "expected paths of functions or methods, e.g. `{} = [Vector2::new, splat]`",
+                name
+            )),
+        })
+        .collect()
+}
+
+/// Returns whether the callable with the given path is listed, that is, whether its path ends
+/// with a listed path. Methods are only given by their name.
+fn lists_call(listed: &[Vec<String>], path: &[String]) -> bool {
+    listed.iter().any(|listed| path.ends_with(listed))
+}
+
+fn path_to_string(path: &syn::Path) -> String {
+    let mut name = Vec::new();
+    if path.leading_colon.is_some() {
+        name.push(String::from("::"));
+    }
+    for p in path.segments.pairs() {
+        match p {
+            syn::punctuated::Pair::Punctuated(ps, _sep) => {
+                name.push(ps.ident.to_string());
+                name.push(String::from("::"));
+            }
+            syn::punctuated::Pair::End(ps) => {
+                name.push(ps.ident.to_string());
+            }
+        }
     }
+    name.concat()
 }
 
 impl<'ast> Visit<'ast> for MacroParameterVisitor {
     fn visit_expr_assign(&mut self, expr: &'ast ExprAssign) {
-        self.visit_expr(&expr.left);
-        self.visit_expr(&expr.right);
+        if let Expr::Path(left) = &*expr.left {
+            self.name = Some(path_to_string(&left.path));
+        }
+        match &*expr.right {
+            Expr::Array(array) => {
+                self.value = Some(ParameterValue::List(array.elems.iter().cloned().collect()))
+            }
+            right @ (Expr::Lit(_) | Expr::Path(_) | Expr::Unary(_)) => self.visit_expr(right),
+            _ => {}
+        }
+        // Values of other kinds, such as float literals or `1 + 2`, are reported by the parameter
+        if self.value.is_none() {
+            self.value = Some(ParameterValue::Other);
+        }
     }
 
     fn visit_expr_path(&mut self, expr: &'ast ExprPath) {
-        let mut name = Vec::new();
-        expr.path
-            .leading_colon
-            .map(|_| name.push(String::from("::")));
-        for p in expr.path.segments.pairs() {
-            match p {
-                syn::punctuated::Pair::Punctuated(ps, _sep) => {
-                    name.push(ps.ident.to_string());
-                    name.push(String::from("::"));
-                }
-                syn::punctuated::Pair::End(ps) => {
-                    name.push(ps.ident.to_string());
-                }
+        self.value = Some(ParameterValue::Path(path_to_string(&expr.path)));
+    }
+
+    fn visit_expr_unary(&mut self, expr: &'ast ExprUnary) {
+        if let (
+            UnOp::Neg(_),
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(lit), ..
+            }),
+        ) = (expr.op, &*expr.expr)
+        {
+            if let Ok(value) = lit.base10_parse::<i128>() {
+                self.value = Some(ParameterValue::Int(-value));
             }
         }
-        self.name = Some(name.concat());
+    }
+
+    fn visit_lit_str(&mut self, expr: &'ast LitStr) {
+        self.value = Some(ParameterValue::Str(expr.value()));
     }
 
     fn visit_lit_bool(&mut self, expr: &'ast LitBool) {
         self.value = Some(ParameterValue::Bool(expr.value));
     }
+
+    fn visit_lit_int(&mut self, expr: &'ast LitInt) {
+        if let Ok(value) = expr.base10_parse() {
+            self.value = Some(ParameterValue::Int(value));
+        }
+    }
 }
 
 enum ParameterValue {
     Bool(bool),
+    Int(i128),
+    Path(String),
+    Str(String),
+    List(Vec<Expr>),
+    /// A value that none of the parameters accepts, such as a float literal
+    Other,
+}
+
+impl ParameterValue {
+    fn into_bool(self, name: &str) -> Result<bool, String> {
+        match self {
+            ParameterValue::Bool(v) => Ok(v),
+            _ => Err(format!("expected a bool for `{}`", name)),
+        }
+    }
+
+    fn into_int(self, name: &str) -> Result<i128, String> {
+        match self {
+            ParameterValue::Int(v) => Ok(v),
+            _ => Err(format!("expected an integer for `{}`", name)),
+        }
+    }
+
+    fn into_path(self, name: &str) -> Result<String, String> {
+        match self {
+            ParameterValue::Path(v) => Ok(v),
+            _ => Err(format!("expected a path for `{}`", name)),
+        }
+    }
+
+    fn into_str(self, name: &str) -> Result<String, String> {
+        match self {
+            ParameterValue::Str(v) => Ok(v),
+            _ => Err(format!("expected a string for `{}`", name)),
+        }
+    }
+
+    fn into_list(self, name: &str) -> Result<Vec<Expr>, String> {
+        match self {
+            ParameterValue::List(v) => Ok(v),
+            _ => Err(format!("expected a list for `{}`", name)),
+        }
+    }
 }
 
-#[derive(Copy, Clone)]
 struct MacroParameters {
     pub visit_macros: bool,
+    /// Power of ten by which literals are multiplied for the `literal_scaled` placeholder, which
+    /// must not be negative:
+    ///
+    /// ```compile_fail
+    /// # use numeric_literals::replace_float_literals;
+    /// #[replace_float_literals(literal_scaled, scale = -1)]
+    /// fn cents() -> i64 {
+    ///     1.5
+    /// }
+    /// ```
+    pub scale: u32,
+    /// Asserts that the replacement expression may be used in `const fn`s
+    pub const_safe: bool,
+    /// Emit an error for float literals that are not exactly representable as `f32`
+    pub deny_inexact_f32: bool,
+    /// Emit a warning for float literals that are not exactly representable as `f32`
+    pub warn_inexact_f32: bool,
+    /// Emit a warning for float literals that are not exactly representable as their own type
+    pub warn_inexact: bool,
+    /// Require integer literals to fit in this type
+    pub fits: Option<IntegerType>,
+    /// Smallest value allowed for integer literals
+    pub min: Option<i128>,
+    /// Largest value allowed for integer literals
+    pub max: Option<i128>,
+    /// Leave suffixed literals untouched
+    pub ignore_suffixed: bool,
+    /// Emit an error for suffixed literals
+    pub deny_suffixed: bool,
+    /// How to handle macro bodies that cannot be parsed as expressions
+    pub macro_fallback: MacroFallback,
+    /// Number of literals that must be replaced
+    pub expect_replacements: Option<usize>,
+    /// Emit a warning for each replaced literal
+    pub trace: bool,
+    /// Emit a warning for each literal that would be replaced, and leave the item unchanged
+    pub dry_run: bool,
+    /// Emit errors instead of warnings for items on which the attribute has no effect
+    pub strict: bool,
+    /// Parsers for the bodies of macros, by the name of the macro
+    pub macro_parsers: Vec<(String, MacroParser)>,
+    /// Bind the replacement of each distinct literal once at the start of function bodies
+    pub hoist: bool,
+    /// Visit the arguments of formatting macros
+    pub visit_format_args: bool,
+    /// Visit `unsafe` blocks
+    pub visit_unsafe: bool,
+    /// Visit inline `const` blocks
+    pub visit_inline_const: bool,
+    /// Visit the literals in `where` clauses
+    pub visit_where_literals: bool,
+    /// Visit const generic arguments, such as the dimensions in `SMatrix<T, 4, 4>`
+    pub visit_generic_args: bool,
+    /// Leave the right-hand sides of shifts untouched
+    pub skip_shift_rhs: bool,
+    /// Leave literals that are endpoints of ranges untouched
+    pub skip_ranges: bool,
+    /// Report literals in indices that would be replaced as errors
+    pub deny_index_replacement: bool,
+    /// Replace unsuffixed integer literals as float literals, if only float literals are replaced
+    pub promote_ints: bool,
+    /// Evaluate expressions that consist solely of float literals before replacing them
+    pub fold_constants: bool,
+    /// Bind each replacement to a variable that marks it in the expanded code
+    pub expansion_markers: bool,
+    /// Where literals are replaced
+    pub context: LiteralContext,
+    /// Paths of the callables to whose arguments replacement is restricted, if any
+    pub only_in_calls: Vec<Vec<String>>,
+    /// Paths of the callables whose arguments are left untouched
+    pub skip_in_calls: Vec<Vec<String>>,
+    /// Names of the `let` bindings to whose initializers replacement is restricted, if any
+    pub only_in_bindings: Vec<String>,
+    /// Names in the replacement expression that intentionally refer to the scope of the literals
+    pub captures: Vec<String>,
+    /// Named replacement expression, used instead of a replacement expression
+    pub preset: Option<Preset>,
+    /// Target type of the preset
+    pub generic: String,
 }
 
 impl Default for MacroParameters {
     fn default() -> Self {
-        Self { visit_macros: true }
+        Self {
+            visit_macros: true,
+            scale: 0,
+            const_safe: false,
+            deny_inexact_f32: false,
+            warn_inexact_f32: false,
+            warn_inexact: false,
+            fits: None,
+            min: None,
+            max: None,
+            ignore_suffixed: false,
+            deny_suffixed: false,
+            macro_fallback: MacroFallback::Skip,
+            expect_replacements: None,
+            trace: false,
+            dry_run: false,
+            strict: false,
+            macro_parsers: Vec::new(),
+            hoist: false,
+            visit_format_args: true,
+            visit_unsafe: true,
+            visit_inline_const: false,
+            visit_where_literals: true,
+            visit_generic_args: true,
+            skip_shift_rhs: false,
+            skip_ranges: false,
+            deny_index_replacement: false,
+            promote_ints: false,
+            fold_constants: false,
+            expansion_markers: false,
+            context: LiteralContext::All,
+            only_in_calls: Vec::new(),
+            skip_in_calls: Vec::new(),
+            only_in_bindings: Vec::new(),
+            captures: Vec::new(),
+            preset: None,
+            generic: String::from("T"),
+        }
     }
 }
 
+/// Describes the expected form of the entries of the `macro_parsers` parameter.
+const MACRO_PARSER_SYNTAX: &str =
+    "expected entries of the form `my_macro = parser` in `macro_parsers = [...]`";
+
 impl MacroParameters {
-    fn set(&mut self, name: &str, value: ParameterValue) {
+    /// Returns the parser registered for the macro, if any.
+    ///
+    /// Macros are recognized by the last segment of their path, like the built-in macros.
+    fn macro_parser(&self, mac: &Macro) -> Option<MacroParser> {
+        let segment = mac.path.segments.last()?;
+        self.macro_parsers
+            .iter()
+            .find(|(name, _)| segment.ident == name)
+            .map(|(_, parser)| *parser)
+    }
+
+    /// Returns whether the pattern binds one of the names listed in `only_in_bindings`.
+    fn lists_binding(&self, pat: &Pat) -> bool {
+        let mut finder = BindingFinder {
+            names: &self.only_in_bindings,
+            found: false,
+        };
+        finder.visit_pat(pat);
+        finder.found
+    }
+
+    /// Sets the parameter `name` to the value. Unknown names are errors:
+    ///
+    /// ```compile_fail
+    /// # use numeric_literals::replace_float_literals;
+    /// #[replace_float_literals(literal, hoistt = true)]
+    /// fn half() -> f64 {
+    ///     0.5
+    /// }
+    /// ```
+    ///
+    /// So are values of the wrong type:
+    ///
+    /// ```compile_fail
+    /// # use numeric_literals::replace_float_literals;
+    /// #[replace_float_literals(literal, hoist = 1)]
+    /// fn half() -> f64 {
+    ///     0.5
+    /// }
+    /// ```
+    ///
+    /// Parameters without a value, such as `hoist`, are reported before they get here:
+    ///
+    /// ```compile_fail
+    /// # use numeric_literals::replace_float_literals;
+    /// #[replace_float_literals(literal, hoist)]
+    /// fn half() -> f64 {
+    ///     0.5
+    /// }
+    /// ```
+    fn set(&mut self, name: &str, value: ParameterValue) -> Result<(), String> {
         match name {
-            "visit_macros" => match value {
-                ParameterValue::Bool(v) => self.visit_macros = v,
-            },
-            _ => {}
+            "visit_macros" => self.visit_macros = value.into_bool(name)?,
+            "scale" => {
+                let v = value.into_int(name)?;
+                if v < 0 {
+                    return Err(format!("`scale` must not be negative, got {}", v));
+                }
+                // Larger scales are reported when a literal does not fit after scaling
+                self.scale = u32::try_from(v).unwrap_or(u32::MAX);
+            }
+            "const_safe" => self.const_safe = value.into_bool(name)?,
+            "deny_inexact_f32" => self.deny_inexact_f32 = value.into_bool(name)?,
+            "warn_inexact_f32" => self.warn_inexact_f32 = value.into_bool(name)?,
+            "warn_inexact" => self.warn_inexact = value.into_bool(name)?,
+            "fits" => {
+                let v = value.into_path(name)?;
+                let integer_type = IntegerType::from_name(&v)
+                    .ok_or_else(|| format!("`{}` is not a primitive integer type", v))?;
+                self.fits = Some(integer_type);
+            }
+            "min" => self.min = Some(value.into_int(name)?),
+            "max" => self.max = Some(value.into_int(name)?),
+            "ignore_suffixed" => self.ignore_suffixed = value.into_bool(name)?,
+            "deny_suffixed" => self.deny_suffixed = value.into_bool(name)?,
+            "trace" => self.trace = value.into_bool(name)?,
+            "dry_run" => self.dry_run = value.into_bool(name)?,
+            "strict" => self.strict = value.into_bool(name)?,
+            "hoist" => self.hoist = value.into_bool(name)?,
+            "visit_format_args" => self.visit_format_args = value.into_bool(name)?,
+            "visit_unsafe" => self.visit_unsafe = value.into_bool(name)?,
+            "visit_inline_const" => self.visit_inline_const = value.into_bool(name)?,
+            "visit_where_literals" => self.visit_where_literals = value.into_bool(name)?,
+            "visit_generic_args" => self.visit_generic_args = value.into_bool(name)?,
+            "skip_shift_rhs" => self.skip_shift_rhs = value.into_bool(name)?,
+            "skip_ranges" => self.skip_ranges = value.into_bool(name)?,
+            "deny_index_replacement" => self.deny_index_replacement = value.into_bool(name)?,
+            "promote_ints" => self.promote_ints = value.into_bool(name)?,
+            "fold_constants" => self.fold_constants = value.into_bool(name)?,
+            "expansion_markers" => self.expansion_markers = value.into_bool(name)?,
+            "preset" => {
+                let v = value.into_str(name)?;
+                self.preset = Some(Preset::from_name(&v).ok_or_else(|| {
+                    format!(
+                        "unknown preset `{}`, expected `from_primitive`, `from` or `as`",
+                        v
+                    )
+                })?);
+            }
+            "generic" => self.generic = value.into_path(name)?,
+            "context" => {
+                let v = value.into_path(name)?;
+                self.context = LiteralContext::from_name(&v).ok_or_else(|| {
+                    format!("unknown context `{}`, expected `all` or `arithmetic`", v)
+                })?;
+            }
+            "macro_fallback" => {
+                let v = value.into_path(name)?;
+                self.macro_fallback = MacroFallback::from_name(&v).ok_or_else(|| {
+                    format!(
+                        "unknown macro fallback `{}`, expected `skip`, `tokens` or `error`",
+                        v
+                    )
+                })?;
+            }
+            "macro_parsers" => {
+                for entry in value.into_list(name)? {
+                    let (name, parser) = match &entry {
+                        Expr::Assign(assign) => match (&*assign.left, &*assign.right) {
+                            (Expr::Path(name), Expr::Path(parser)) => {
+                                (path_to_string(&name.path), path_to_string(&parser.path))
+                            }
+                            _ => return Err(MACRO_PARSER_SYNTAX.to_string()),
+                        },
+                        _ => return Err(MACRO_PARSER_SYNTAX.to_string()),
+                    };
+                    let parser = MacroParser::from_name(&parser).ok_or_else(|| {
+                        format!(
+                            "unknown macro parser `{}`, expected `expressions`, `format`, `pattern`, `tokens` or `skip`",
+                            parser
+                        )
+                    })?;
+                    self.macro_parsers.push((name, parser));
+                }
+            }
+            "only_in_calls" => self.only_in_calls = call_paths(value.into_list(name)?, name)?,
+            "skip_in_calls" => self.skip_in_calls = call_paths(value.into_list(name)?, name)?,
+            "only_in_bindings" => self.only_in_bindings = names(value.into_list(name)?, name)?,
+            "captures" => self.captures = names(value.into_list(name)?, name)?,
+            "expect_replacements" => {
+                let v = value.into_int(name)?;
+                let count = usize::try_from(v).map_err(|_| {
+                    format!("`expect_replacements` must not be negative, got {}", v)
+                })?;
+                self.expect_replacements = Some(count);
+            }
+            _ => return Err(format!("unknown parameter `{}`", name)),
         }
+        Ok(())
     }
 }
 
-/// Obtain the replacement expression and parameters from the macro attr token stream.
-fn parse_macro_attribute(attr: TokenStream) -> Result<(Expr, MacroParameters), syn::Error> {
-    let parser = Punctuated::<Expr, Token![,]>::parse_separated_nonempty;
-    let attributes = parser.parse(attr)?;
+/// Emits the transformed item, along with any errors and warnings encountered while transforming it.
+fn expand(mut item: Item, errors: Vec<syn::Error>, warnings: Vec<Warning>) -> TokenStream {
+    let warnings = attach_warnings(&mut item, &warnings);
+    let errors = errors.into_iter().map(|err| err.to_compile_error());
+    let expanded = quote! {
+        #item
+        #warnings
+        #(#errors)*
+    };
+
+    TokenStream::from(remove_markers(expanded))
+}
+
+/// The replacement expressions of float and integer literals, which only differ for presets.
+struct Replacements {
+    float: Replacement,
+    int: Replacement,
+}
 
-    let mut attr_iter = attributes.into_iter();
-    let replacement = attr_iter.next().expect("No replacement provided");
+/// Obtain the replacement expressions and parameters from the macro attr token stream.
+///
+/// The replacement expression is omitted if the attribute starts with a parameter, in which case
+/// it is given by the `preset` parameter.
+fn parse_macro_attribute(
+    attr: proc_macro2::TokenStream,
+) -> Result<(Replacements, MacroParameters), syn::Error> {
+    let parser = |input: ParseStream| {
+        let starts_with_parameter =
+            input.peek(syn::Ident) && input.peek2(Token![=]) && !input.peek2(Token![==]);
+        let replacement: Option<Replacement> = if starts_with_parameter {
+            None
+        } else {
+            Some(input.parse()?)
+        };
+        let mut parameter_exprs = Vec::new();
+        while !input.is_empty() {
+            if replacement.is_some() || !parameter_exprs.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+            if !input.is_empty() {
+                parameter_exprs.push(input.parse::<Expr>()?);
+            }
+        }
+        Ok((replacement, parameter_exprs))
+    };
+    let (replacement, parameter_exprs) = parser.parse2(attr)?;
 
-    let user_parameters: Vec<_> = attr_iter
-        .filter_map(|expr| MacroParameterVisitor::parse_flag(&expr))
-        .collect();
     let mut parameters = MacroParameters::default();
-    for (name, value) in user_parameters {
-        parameters.set(&name, value);
+    for expr in parameter_exprs {
+        let (name, value) = MacroParameterVisitor::parse_flag(&expr).ok_or_else(|| {
+            syn::Error::new_spanned(&expr, "expected a parameter of the form `name = value`")
+        })?;
+        parameters
+            .set(&name, value)
+            .map_err(|message| syn::Error::new_spanned(&expr, message))?;
+    }
+
+    if parameters.ignore_suffixed && parameters.deny_suffixed {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "`ignore_suffixed` and `deny_suffixed` cannot be used together",
+        ));
+    }
+
+    let replacements = match (replacement, parameters.preset) {
+        (Some(replacement), None) => {
+            let replacement = expand_function_path(replacement);
+            Replacements {
+                float: replacement.clone(),
+                int: replacement,
+            }
+        }
+        (None, Some(preset)) => {
+            let generic: Type = syn::parse_str(&parameters.generic).map_err(|_| {
+                syn::Error::new(
+                    Span::call_site(),
+                    format!("`generic = {}` is not a type", parameters.generic),
+                )
+            })?;
+            Replacements {
+                float: Replacement::Single(Box::new(preset.replacement(&generic, true))),
+                int: Replacement::Single(Box::new(preset.replacement(&generic, false))),
+            }
+        }
+        (Some(_), Some(_)) => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "a `preset` cannot be combined with a replacement expression",
+            ))
+        }
+        (None, None) => {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "expected a replacement expression or a `preset`",
+            ))
+        }
+    };
+
+    Ok((replacements, parameters))
+}
+
+/// Expands a replacement that is just a path to a function, e.g. `nalgebra::convert` or
+/// `T::from_f64`, into a call of the function with the literal.
+///
+/// Functions are told apart from constants such as `N` by their lowercase names. Placeholders and
+/// primitive types, whose suffix is attached to the literals, are left as they are.
+fn expand_function_path(replacement: Replacement) -> Replacement {
+    if let Replacement::Single(expr) = &replacement {
+        if let Expr::Path(path_expr) = &**expr {
+            let is_function = matches!(
+                path_expr.path.segments.last(),
+                Some(segment) if {
+                    let name = segment.ident.to_string();
+                    name.starts_with(|c: char| c.is_lowercase())
+                        && Placeholder::from_name(&name, "literal").is_none()
+                }
+            );
+            if is_function && PrimitiveSuffix::from_replacement(&replacement).is_none() {
+                return Replacement::Single(Box::new(parse_quote!(#path_expr(literal))));
+            }
+        }
     }
+    replacement
+}
 
-    Ok((replacement, parameters))
+/// Checks the number of replaced literals against the `expect_replacements` parameter.
+fn check_replacement_count(
+    parameters: &MacroParameters,
+    count: usize,
+    errors: &mut Vec<syn::Error>,
+) {
+    match parameters.expect_replacements {
+        Some(expected) if expected != count => errors.push(syn::Error::new(
+            Span::call_site(),
+            format!(
+                "expected {} literals to be replaced, but {} were replaced",
+                expected, count
+            ),
+        )),
+        _ => {}
+    }
 }
 
-/// Replace any numeric literal with custom transformation code.
+/// Replaces the literals of the item according to one attribute of this crate.
 ///
-/// Refer to the documentation at the root of the crate for usage instructions.
-#[proc_macro_attribute]
-pub fn replace_numeric_literals(attr: TokenStream, item: TokenStream) -> TokenStream {
+/// The item itself carries the stacked attributes of this crate, so it is always visited, while
+/// nested items with their own attribute are not.
+fn apply_attribute(
+    kind: AttributeKind,
+    attr: proc_macro2::TokenStream,
+    item: &mut Item,
+    aliases: &[String],
+    errors: &mut Vec<syn::Error>,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), syn::Error> {
+    let (replacements, parameters) = parse_macro_attribute(attr)?;
+    check_has_expressions(&parameters, item, errors, warnings);
+    check_free_identifiers(&replacements, &parameters, warnings);
+    // The replacements of a preset are known to be valid, and the names of primitive types are
+    // attached to the literals as suffixes
+    if parameters.preset.is_none()
+        && PrimitiveSuffix::from_replacement(&replacements.float).is_none()
+    {
+        let constant = matches!(
+            kind,
+            AttributeKind::Numeric(LiteralValues::Zero)
+                | AttributeKind::Numeric(LiteralValues::One)
+        );
+        validate_replacement(
+            &replacements.float,
+            "literal",
+            constant,
+            parameters.strict,
+            warnings,
+        )?;
+    }
+    let float_template = replacements
+        .float
+        .map(|expr| Template::new(expr, "literal"));
+    let int_template = replacements.int.map(|expr| Template::new(expr, "literal"));
+    let templates = [&float_template, &int_template];
+    let per_occurrence = [
+        (Placeholder::Index, "index"),
+        (Placeholder::Line, "line"),
+        (Placeholder::Column, "column"),
+    ];
+    for (placeholder, name) in per_occurrence.iter() {
+        if parameters.hoist
+            && templates
+                .iter()
+                .flat_map(|template| template.expressions())
+                .any(|template| template.contains(*placeholder))
+        {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                format!(
+                    "`hoist = true` cannot be used with the `literal_{}` placeholder, \
+                     since the {} differs between occurrences of the same literal",
+                    name, name
+                ),
+            ));
+        }
+    }
+    let item_name = item_name(item);
+    let (float_replacement, int_replacement, values) = match kind {
+        AttributeKind::Numeric(values) => (Some(&float_template), Some(&int_template), values),
+        AttributeKind::Float => (Some(&float_template), None, LiteralValues::All),
+        AttributeKind::Int(values) => (None, Some(&int_template), values),
+    };
+    let mut replacer = LiteralVisitor {
+        parameters: &parameters,
+        attribute_aliases: aliases,
+        float_replacement,
+        int_replacement,
+        float_suffix: PrimitiveSuffix::from_replacement(&replacements.float),
+        int_suffix: PrimitiveSuffix::from_replacement(&replacements.int),
+        values,
+        errors: Vec::new(),
+        warnings: Vec::new(),
+        current_index: 0,
+        item_name: &item_name,
+        hoisted: None,
+        operand: false,
+        in_listed_call: false,
+        in_skipped_call: false,
+        in_listed_binding: false,
+        in_length: false,
+        in_foreign_item: false,
+        in_index: false,
+    };
+    replacer.warnings = const_fn_warnings(item, &replacements, &parameters, aliases, |class| {
+        replacer.replaces(class)
+    });
+    let original = if parameters.dry_run {
+        Some(item.clone())
+    } else {
+        None
+    };
+    match item {
+        Item::Macro(item_macro) if item_macro.mac.path.is_ident("macro_rules") => {
+            visit_macro_rules_mut(&mut replacer, &mut item_macro.mac)
+        }
+        _ => visit_item_mut(&mut replacer, item),
+    }
+    if let Some(original) = original {
+        *item = original;
+    }
+    check_replacement_count(&parameters, replacer.current_index, &mut replacer.errors);
+    errors.append(&mut replacer.errors);
+    warnings.append(&mut replacer.warnings);
+    Ok(())
+}
+
+/// Expands an attribute of this crate.
+///
+/// Further attributes of this crate stacked below it on the same item are applied in the same
+/// expansion, in order. Each of them only replaces the literals written in the item, and not the
+/// literals in the replacements of the attributes applied before it.
+fn replace_literals(kind: AttributeKind, attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attr = proc_macro2::TokenStream::from(attr);
+    if is_applied(&attr) {
+        return item;
+    }
     let mut input = parse_macro_input!(item as Item);
-    let (replacement, parameters) = match parse_macro_attribute(attr) {
-        Ok(res) => res,
+    let stacked = match take_stacked_attributes(&mut input) {
+        Ok(stacked) => stacked,
         Err(err) => return TokenStream::from(err.to_compile_error()),
     };
+    let aliases = attribute_aliases(&input);
 
-    let mut replacer = NumericLiteralVisitor {
-        parameters,
-        placeholder: "literal",
-        int_replacement: &replacement,
-        float_replacement: &replacement,
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    for (kind, attr) in std::iter::once((kind, attr)).chain(stacked) {
+        if let Err(err) =
+            apply_attribute(kind, attr, &mut input, &aliases, &mut errors, &mut warnings)
+        {
+            return TokenStream::from(err.to_compile_error());
+        }
+    }
+
+    expand(input, errors, warnings)
+}
+
+/// Name of the module in which the items of `apply_to_module!` are visited.
+const MODULE_NAME: &str = "__numeric_literals_module";
+
+/// The body of `apply_to_module!`: inner attributes of this crate, followed by items.
+struct ModuleBody {
+    attrs: Vec<Attribute>,
+    items: Vec<Item>,
+}
+
+impl Parse for ModuleBody {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_inner)?;
+        if attrs.is_empty() {
+            return Err(input.error(
+                "expected inner attributes of this crate, e.g. `#![replace_float_literals(...)]`",
+            ));
+        }
+        let mut items = Vec::new();
+        while !input.is_empty() {
+            items.push(input.parse()?);
+        }
+        Ok(ModuleBody { attrs, items })
+    }
+}
+
+/// Expands `apply_to_module!`.
+///
+/// The items are visited as the items of a module with the given attributes, so that items with
+/// their own attribute of this crate are left to it, and are then emitted in place of the macro.
+fn apply_to_module_items(body: ModuleBody) -> Result<proc_macro2::TokenStream, syn::Error> {
+    let mut attributes = Vec::new();
+    for attr in &body.attrs {
+        match AttributeKind::of(attr) {
+            Some(kind) => attributes.push((kind, attr.parse_args()?)),
+            None => {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "only attributes of this crate can be applied to the items",
+                ))
+            }
+        }
+    }
+    let module_name = Ident::new(MODULE_NAME, Span::call_site());
+    let items = body.items;
+    let mut module: Item = parse_quote!(mod #module_name { #(#items)* });
+    let aliases = attribute_aliases(&module);
+
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    for (kind, attr) in attributes {
+        apply_attribute(
+            kind,
+            attr,
+            &mut module,
+            &aliases,
+            &mut errors,
+            &mut warnings,
+        )?;
+    }
+
+    let warnings = attach_warnings(&mut module, &warnings);
+    let errors = errors.into_iter().map(|err| err.to_compile_error());
+    let items = match module {
+        Item::Mod(module) => module.content.map(|(_, items)| items).unwrap_or_default(),
+        _ => unreachable!("the items are visited in a module"),
     };
-    replacer.visit_item_mut(&mut input);
+    Ok(remove_markers(quote! {
+        #(#items)*
+        #warnings
+        #(#errors)*
+    }))
+}
 
-    let expanded = quote! { #input };
+/// Apply the attributes of this crate to all items in the body, as if they were the attributes of
+/// an enclosing module.
+///
+/// The attributes are given as inner attributes at the start of the body, such as
+/// `#![replace_float_literals(T::from(literal).unwrap())]`. Refer to the documentation at the
+/// root of the crate for usage instructions.
+#[proc_macro]
+pub fn apply_to_module(input: TokenStream) -> TokenStream {
+    let body = parse_macro_input!(input as ModuleBody);
+    match apply_to_module_items(body) {
+        Ok(tokens) => TokenStream::from(tokens),
+        Err(err) => TokenStream::from(err.to_compile_error()),
+    }
+}
 
-    TokenStream::from(expanded)
+/// Replace any numeric literal with custom transformation code.
+///
+/// Refer to the documentation at the root of the crate for usage instructions.
+#[proc_macro_attribute]
+pub fn replace_numeric_literals(attr: TokenStream, item: TokenStream) -> TokenStream {
+    replace_literals(AttributeKind::Numeric(LiteralValues::All), attr, item)
 }
 
-/// Replace any float literal with custom transformation code.
+/// Replace any numeric literal whose value is zero with custom transformation code.
 ///
 /// Refer to the documentation at the root of the crate for usage instructions.
 #[proc_macro_attribute]
-pub fn replace_float_literals(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let mut input = parse_macro_input!(item as Item);
-    let (replacement, parameters) = match parse_macro_attribute(attr) {
-        Ok(res) => res,
-        Err(err) => return TokenStream::from(err.to_compile_error()),
-    };
+pub fn replace_zero_literals(attr: TokenStream, item: TokenStream) -> TokenStream {
+    replace_literals(AttributeKind::Numeric(LiteralValues::Zero), attr, item)
+}
 
-    let mut replacer = FloatLiteralVisitor {
-        parameters,
-        placeholder: "literal",
-        replacement: &replacement,
-    };
-    replacer.visit_item_mut(&mut input);
+/// Replace any numeric literal whose value is one with custom transformation code.
+///
+/// Refer to the documentation at the root of the crate for usage instructions.
+#[proc_macro_attribute]
+pub fn replace_one_literals(attr: TokenStream, item: TokenStream) -> TokenStream {
+    replace_literals(AttributeKind::Numeric(LiteralValues::One), attr, item)
+}
 
-    let expanded = quote! { #input };
+/// Replace any numeric literal whose value is not zero with custom transformation code.
+///
+/// Refer to the documentation at the root of the crate for usage instructions.
+#[proc_macro_attribute]
+pub fn replace_nonzero_literals(attr: TokenStream, item: TokenStream) -> TokenStream {
+    replace_literals(AttributeKind::Numeric(LiteralValues::NonZero), attr, item)
+}
 
-    TokenStream::from(expanded)
+/// Replace any float literal with custom transformation code.
+///
+/// Refer to the documentation at the root of the crate for usage instructions.
+#[proc_macro_attribute]
+pub fn replace_float_literals(attr: TokenStream, item: TokenStream) -> TokenStream {
+    replace_literals(AttributeKind::Float, attr, item)
 }
 
 /// Replace any integer literal with custom transformation code.
@@ -463,20 +4071,14 @@ pub fn replace_float_literals(attr: TokenStream, item: TokenStream) -> TokenStre
 /// Refer to the documentation at the root of the crate for usage instructions.
 #[proc_macro_attribute]
 pub fn replace_int_literals(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let mut input = parse_macro_input!(item as Item);
-    let (replacement, parameters) = match parse_macro_attribute(attr) {
-        Ok(res) => res,
-        Err(err) => return TokenStream::from(err.to_compile_error()),
-    };
-
-    let mut replacer = IntLiteralVisitor {
-        parameters,
-        placeholder: "literal",
-        replacement: &replacement,
-    };
-    replacer.visit_item_mut(&mut input);
-
-    let expanded = quote! { #input };
+    replace_literals(AttributeKind::Int(LiteralValues::All), attr, item)
+}
 
-    TokenStream::from(expanded)
+/// Replace any integer literal with an unsigned suffix, such as `0xFFu8`, with custom
+/// transformation code.
+///
+/// Refer to the documentation at the root of the crate for usage instructions.
+#[proc_macro_attribute]
+pub fn replace_uint_literals(attr: TokenStream, item: TokenStream) -> TokenStream {
+    replace_literals(AttributeKind::Int(LiteralValues::Unsigned), attr, item)
 }
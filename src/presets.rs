@@ -0,0 +1,55 @@
+//! Named replacement expressions for the `preset` parameter.
+//!
+//! A preset expands to one of the common replacement expressions, with the target type given by
+//! the `generic` parameter. Since the literal is passed as is, the expression may differ between
+//! float and integer literals.
+
+use proc_macro2::Span;
+use syn::{parse_quote, Expr, LitStr, Type};
+
+/// A named replacement expression.
+#[derive(Copy, Clone)]
+pub enum Preset {
+    /// `T::from_f64(literal)` or `T::from_u64(literal)` from `num_traits::FromPrimitive`
+    FromPrimitive,
+    /// `T::from(literal)` from `std::convert::From`
+    From,
+    /// `literal as T`
+    As,
+}
+
+impl Preset {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "from_primitive" => Some(Preset::FromPrimitive),
+            "from" => Some(Preset::From),
+            "as" => Some(Preset::As),
+            _ => None,
+        }
+    }
+
+    /// The replacement expression of float literals, or of integer literals if `float` is false.
+    ///
+    /// Literals are never negative, since the negation is not part of the literal, so the
+    /// conversion of integer literals is from `u64`.
+    pub fn replacement(self, generic: &Type, float: bool) -> Expr {
+        match self {
+            Preset::FromPrimitive => {
+                let message = LitStr::new(
+                    &format!(
+                        "literal must fit in {}",
+                        quote::ToTokens::to_token_stream(generic)
+                    ),
+                    Span::call_site(),
+                );
+                if float {
+                    parse_quote!(<#generic>::from_f64(literal).expect(#message))
+                } else {
+                    parse_quote!(<#generic>::from_u64(literal).expect(#message))
+                }
+            }
+            Preset::From => parse_quote!(<#generic>::from(literal)),
+            Preset::As => parse_quote!(literal as #generic),
+        }
+    }
+}
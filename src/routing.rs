@@ -0,0 +1,288 @@
+//! Selection of the replacement expression by the value of the literal.
+//!
+//! Instead of a single expression, the replacement may be given as
+//! `match_value { 0.0..1.0 => f32::from(literal), _ => f64::from(literal) }`. The arms are
+//! evaluated at expansion time against the exact value of each literal, and the expression of
+//! the first matching arm is used as the replacement for that literal. A `match` on the literal
+//! itself, as in `match literal { 0 => T::zero(), _ => T::from(literal).unwrap() }`, is handled in
+//! the same way, so that no match is left in the expanded code. This is only done if every arm
+//! can be decided from the value of the literal, and otherwise the match is evaluated at runtime.
+
+use crate::decimal::Decimal;
+use quote::ToTokens;
+use std::cmp::Ordering;
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::{braced, Arm, Expr, ExprLit, Pat, RangeLimits, UnOp};
+
+/// Keyword introducing a replacement that is selected by the value of the literal.
+const MATCH_VALUE: &str = "match_value";
+
+/// Name of the literal in the replacement expression, on which a `match` selects the replacement.
+const LITERAL: &str = "literal";
+
+/// The replacement expression given to one of the macros.
+///
+/// Once parsed, the expressions may be converted to another representation with `map`.
+#[derive(Clone)]
+pub enum Replacement<T = Expr> {
+    Single(Box<T>),
+    Routed(Vec<Route<T>>),
+}
+
+/// An arm of a `match_value` replacement.
+#[derive(Clone)]
+pub struct Route<T = Expr> {
+    pattern: ValuePattern,
+    replacement: T,
+}
+
+/// A pattern over literal values. Only the patterns that can be decided from the value of a
+/// literal are supported.
+#[derive(Clone)]
+enum ValuePattern {
+    Any,
+    Value(Value),
+    Range {
+        lo: Value,
+        hi: Value,
+        inclusive: bool,
+    },
+    Or(Vec<ValuePattern>),
+}
+
+/// An exact signed value, as it may appear as a bound in a pattern.
+#[derive(Clone, PartialEq, Eq)]
+struct Value {
+    negative: bool,
+    magnitude: Decimal,
+}
+
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, false) => self.magnitude.cmp(&other.magnitude),
+            (true, true) => other.magnitude.cmp(&self.magnitude),
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Value {
+    fn from_expr(expr: &Expr) -> Result<Self, syn::Error> {
+        let (negative, lit) = match expr {
+            Expr::Unary(unary) if matches!(unary.op, UnOp::Neg(_)) => (true, &*unary.expr),
+            _ => (false, expr),
+        };
+        let magnitude = match lit {
+            Expr::Lit(lit) => Decimal::from_lit(&lit.lit),
+            _ => None,
+        }
+        .ok_or_else(|| syn::Error::new_spanned(expr, "expected a numeric literal"))?;
+        // Treat -0 like 0, so that the order is consistent with equality
+        let negative = negative && !magnitude.is_zero();
+        Ok(Value {
+            negative,
+            magnitude,
+        })
+    }
+}
+
+impl ValuePattern {
+    fn from_pat(pat: &Pat, syntax: &str) -> Result<Self, syn::Error> {
+        match pat {
+            Pat::Wild(_) => Ok(ValuePattern::Any),
+            Pat::Lit(lit) => Ok(ValuePattern::Value(Value::from_expr(&lit.expr)?)),
+            Pat::Range(range) => Ok(ValuePattern::Range {
+                lo: Value::from_expr(&range.lo)?,
+                hi: Value::from_expr(&range.hi)?,
+                inclusive: matches!(range.limits, RangeLimits::Closed(_)),
+            }),
+            Pat::Or(or) => or
+                .cases
+                .iter()
+                .map(|case| ValuePattern::from_pat(case, syntax))
+                .collect::<Result<_, _>>()
+                .map(ValuePattern::Or),
+            _ => Err(syn::Error::new(
+                pat.span(),
+                format!(
+                    "unsupported pattern in `{}`, expected `_`, a numeric literal or a range",
+                    syntax
+                ),
+            )),
+        }
+    }
+
+    fn matches(&self, value: &Value) -> bool {
+        match self {
+            ValuePattern::Any => true,
+            ValuePattern::Value(v) => v == value,
+            ValuePattern::Range { lo, hi, inclusive } => {
+                lo <= value && if *inclusive { value <= hi } else { value < hi }
+            }
+            ValuePattern::Or(patterns) => patterns.iter().any(|p| p.matches(value)),
+        }
+    }
+}
+
+impl Parse for Replacement {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let is_routed = matches!(input.fork().parse::<syn::Ident>(), Ok(ident) if ident == MATCH_VALUE)
+            && input.peek2(syn::token::Brace);
+        if !is_routed {
+            return match input.parse()? {
+                Expr::Match(expr_match)
+                    if is_literal(&expr_match.expr) && is_decidable(&expr_match.arms) =>
+                {
+                    Replacement::routed(expr_match.arms, "match literal", input)
+                }
+                expr => Ok(Replacement::Single(Box::new(expr))),
+            };
+        }
+
+        input.parse::<syn::Ident>()?;
+        let content;
+        braced!(content in input);
+        let mut arms = Vec::new();
+        while !content.is_empty() {
+            arms.push(content.parse()?);
+        }
+        Replacement::routed(arms, MATCH_VALUE, input)
+    }
+}
+
+/// Returns whether the expression is the literal itself, as in `match literal { ... }`.
+fn is_literal(expr: &Expr) -> bool {
+    matches!(expr, Expr::Path(path) if path.qself.is_none() && path.path.is_ident(LITERAL))
+}
+
+/// Returns whether the arms of a `match literal` can be decided from the value of the literal,
+/// i.e. whether there are arms, and none of them has a guard or a pattern such as a binding.
+fn is_decidable(arms: &[Arm]) -> bool {
+    !arms.is_empty()
+        && arms.iter().all(|arm| {
+            arm.guard.is_none() && ValuePattern::from_pat(&arm.pat, "match literal").is_ok()
+        })
+}
+
+impl Replacement {
+    /// Converts the arms of `syntax`, i.e. `match_value` or `match literal`, to routes.
+    fn routed(arms: Vec<Arm>, syntax: &str, input: ParseStream) -> syn::Result<Self> {
+        let mut routes = Vec::new();
+        for arm in arms {
+            if let Some((if_token, _)) = &arm.guard {
+                return Err(syn::Error::new_spanned(
+                    if_token,
+                    format!("guards are not supported in `{}`", syntax),
+                ));
+            }
+            routes.push(Route {
+                pattern: ValuePattern::from_pat(&arm.pat, syntax)?,
+                replacement: *arm.body,
+            });
+        }
+        if routes.is_empty() {
+            return Err(input.error(format!("`{}` requires at least one arm", syntax)));
+        }
+        Ok(Replacement::Routed(routes))
+    }
+}
+
+impl<T> Replacement<T> {
+    /// All the expressions that may be used as replacement.
+    pub fn expressions(&self) -> Vec<&T> {
+        match self {
+            Replacement::Single(expr) => vec![&**expr],
+            Replacement::Routed(routes) => routes.iter().map(|route| &route.replacement).collect(),
+        }
+    }
+
+    /// Converts each of the expressions, keeping the arms of a `match_value` replacement.
+    pub fn map<U, F: FnMut(&T) -> U>(&self, mut f: F) -> Replacement<U> {
+        match self {
+            Replacement::Single(expr) => Replacement::Single(Box::new(f(expr))),
+            Replacement::Routed(routes) => Replacement::Routed(
+                routes
+                    .iter()
+                    .map(|route| Route {
+                        pattern: route.pattern.clone(),
+                        replacement: f(&route.replacement),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Selects the replacement expression for the literal.
+    pub fn select(&self, literal: &ExprLit) -> Result<&T, syn::Error> {
+        let routes = match self {
+            Replacement::Single(expr) => return Ok(expr),
+            Replacement::Routed(routes) => routes,
+        };
+        let magnitude = Decimal::from_lit(&literal.lit).ok_or_else(|| {
+            syn::Error::new_spanned(
+                literal,
+                format!(
+                    "cannot determine the exact value of the literal `{}` to select an arm",
+                    literal.lit.to_token_stream()
+                ),
+            )
+        })?;
+        let value = Value {
+            negative: false,
+            magnitude,
+        };
+        routes
+            .iter()
+            .find(|route| route.pattern.matches(&value))
+            .map(|route| &route.replacement)
+            .ok_or_else(|| {
+                syn::Error::new_spanned(
+                    literal,
+                    format!(
+                        "no arm of the replacement matches the literal `{}`",
+                        literal.lit.to_token_stream()
+                    ),
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Replacement;
+    use syn::ExprLit;
+
+    #[test]
+    fn select_by_value() {
+        let replacement: Replacement =
+            syn::parse_str("match_value { 0..1 => small, 1 | 2 => one_or_two, _ => large }")
+                .unwrap();
+        let select = |literal: &str| {
+            let literal: ExprLit = syn::parse_str(literal).unwrap();
+            let selected = replacement.select(&literal).unwrap();
+            quote::ToTokens::to_token_stream(selected).to_string()
+        };
+        assert_eq!(select("0.5"), "small");
+        assert_eq!(select("2.0"), "one_or_two");
+        assert_eq!(select("1e3"), "large");
+    }
+
+    #[test]
+    fn select_reports_literal_with_unrepresentable_exponent() {
+        let replacement: Replacement = syn::parse_str("match_value { _ => literal }").unwrap();
+        let literal: ExprLit = syn::parse_str("1e99999999999999999999").unwrap();
+        let error = replacement.select(&literal).err().unwrap();
+        assert!(error
+            .to_string()
+            .contains("cannot determine the exact value"));
+    }
+}
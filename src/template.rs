@@ -0,0 +1,228 @@
+//! Instantiation of the replacement expression for each literal.
+//!
+//! An item may contain thousands of literals, for instance in generated lookup tables. Rather
+//! than cloning and visiting the replacement expression for every literal, it is converted once
+//! into a template of tokens in which the placeholders are marked, and every literal only
+//! requires a single pass over the template.
+
+use proc_macro2::{Delimiter, Group, Ident, Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::visit_mut::{visit_expr_mut, VisitMut};
+use syn::{Expr, ExprParen};
+
+/// Prefix of the identifiers that temporarily stand in for the placeholders of a template.
+const SLOT_PREFIX: &str = "__numeric_literals_slot_";
+
+/// The placeholders that may appear in a replacement expression.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Placeholder {
+    /// The literal itself, e.g. `literal`
+    Literal,
+    /// The literal multiplied by `10^scale`, e.g. `literal_scaled`
+    Scaled,
+    /// The kind of the literal, e.g. `literal_kind`
+    Kind,
+    /// The index of the literal among the replaced literals, e.g. `literal_index`
+    Index,
+    /// The source text of the literal, e.g. `literal_text`
+    Text,
+    /// The literal as a float literal, e.g. `literal_float`
+    Float,
+    /// The source file of the literal, e.g. `literal_file`
+    File,
+    /// The line of the literal, e.g. `literal_line`
+    Line,
+    /// The column of the literal, e.g. `literal_column`
+    Column,
+}
+
+impl Placeholder {
+    /// Returns the placeholder denoted by the identifier, where `placeholder` is the name of the
+    /// literal itself and derived placeholders consist of that name followed by a suffix.
+    pub fn from_name(name: &str, placeholder: &str) -> Option<Self> {
+        match name.strip_prefix(placeholder)? {
+            "" => Some(Placeholder::Literal),
+            "_scaled" => Some(Placeholder::Scaled),
+            "_kind" => Some(Placeholder::Kind),
+            "_index" => Some(Placeholder::Index),
+            "_text" => Some(Placeholder::Text),
+            "_float" => Some(Placeholder::Float),
+            "_file" => Some(Placeholder::File),
+            "_line" => Some(Placeholder::Line),
+            "_column" => Some(Placeholder::Column),
+            _ => None,
+        }
+    }
+}
+
+/// A token of a template.
+enum Piece {
+    Token(TokenTree),
+    Group(Delimiter, Span, Vec<Piece>),
+    Placeholder(Placeholder),
+}
+
+/// A replacement expression, prepared for instantiation with many literals.
+pub struct Template {
+    pieces: Vec<Piece>,
+}
+
+/// Replaces the placeholders in the replacement expression with numbered slots.
+struct SlotVisitor<'a> {
+    placeholder: &'a str,
+    slots: Vec<Placeholder>,
+}
+
+impl<'a> VisitMut for SlotVisitor<'a> {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if let Expr::Path(path_expr) = expr {
+            if let Some(last_segment) = path_expr.path.segments.last() {
+                let name = last_segment.ident.to_string();
+                if let Some(placeholder) = Placeholder::from_name(&name, self.placeholder) {
+                    let slot = Ident::new(
+                        &format!("{}{}", SLOT_PREFIX, self.slots.len()),
+                        last_segment.ident.span(),
+                    );
+                    self.slots.push(placeholder);
+                    *expr = Expr::Path(syn::parse_quote!(#slot));
+                    return;
+                }
+            }
+        }
+        visit_expr_mut(self, expr)
+    }
+}
+
+impl Template {
+    /// Prepares the replacement expression, in which `placeholder` is the name of the literal.
+    pub fn new(expr: &Expr, placeholder: &str) -> Self {
+        let mut visitor = SlotVisitor {
+            placeholder,
+            slots: Vec::new(),
+        };
+        let mut expr = expr.clone();
+        visitor.visit_expr_mut(&mut expr);
+        // A slot is atomic like the literal it stands for, so the parentheses do not depend on it
+        let tokens = parenthesized(expr).into_token_stream();
+        Template {
+            pieces: pieces(tokens, &visitor.slots),
+        }
+    }
+
+    /// Returns whether the placeholder occurs in the replacement expression.
+    pub fn contains(&self, placeholder: Placeholder) -> bool {
+        fn contains(pieces: &[Piece], placeholder: Placeholder) -> bool {
+            pieces.iter().any(|piece| match piece {
+                Piece::Token(_) => false,
+                Piece::Group(_, _, pieces) => contains(pieces, placeholder),
+                Piece::Placeholder(p) => *p == placeholder,
+            })
+        }
+        contains(&self.pieces, placeholder)
+    }
+
+    /// Instantiates the template for a literal at the given location.
+    ///
+    /// The tokens of the replacement expression are written in the attribute, so compiler errors
+    /// in the replacement (e.g. an unsatisfied trait bound) would otherwise point at the attribute
+    /// rather than at the offending literal. All tokens are therefore moved to the location of the
+    /// literal. Only the location changes, so names in the replacement still resolve as they were
+    /// written.
+    pub fn instantiate<F>(&self, span: Span, mut substitute: F) -> Result<TokenStream, syn::Error>
+    where
+        F: FnMut(Placeholder) -> Result<TokenStream, syn::Error>,
+    {
+        let mut tokens = TokenStream::new();
+        instantiate_pieces(&self.pieces, span, &mut substitute, &mut tokens)?;
+        Ok(tokens)
+    }
+}
+
+/// Converts the tokens of a replacement expression to pieces, resolving the numbered slots.
+fn pieces(tokens: TokenStream, slots: &[Placeholder]) -> Vec<Piece> {
+    tokens
+        .into_iter()
+        .map(|token| match token {
+            TokenTree::Group(group) => Piece::Group(
+                group.delimiter(),
+                group.span(),
+                pieces(group.stream(), slots),
+            ),
+            TokenTree::Ident(ident) => {
+                let name = ident.to_string();
+                match name
+                    .strip_prefix(SLOT_PREFIX)
+                    .and_then(|index| index.parse::<usize>().ok())
+                {
+                    Some(index) => Piece::Placeholder(slots[index]),
+                    None => Piece::Token(TokenTree::Ident(ident)),
+                }
+            }
+            token => Piece::Token(token),
+        })
+        .collect()
+}
+
+fn instantiate_pieces<F>(
+    pieces: &[Piece],
+    span: Span,
+    substitute: &mut F,
+    output: &mut TokenStream,
+) -> Result<(), syn::Error>
+where
+    F: FnMut(Placeholder) -> Result<TokenStream, syn::Error>,
+{
+    for piece in pieces {
+        match piece {
+            Piece::Token(token) => {
+                let mut token = token.clone();
+                // Literals do not resolve names, so they are spanned as macro output instead.
+                // Otherwise lints would check them against the source text of the replaced literal.
+                let resolved_at = match token {
+                    TokenTree::Literal(_) => Span::call_site(),
+                    _ => token.span(),
+                };
+                token.set_span(resolved_at.located_at(span));
+                output.extend(Some(token));
+            }
+            Piece::Group(delimiter, group_span, pieces) => {
+                let mut stream = TokenStream::new();
+                instantiate_pieces(pieces, span, substitute, &mut stream)?;
+                let mut group = Group::new(*delimiter, stream);
+                group.set_span(group_span.located_at(span));
+                output.extend(Some(TokenTree::Group(group)));
+            }
+            Piece::Placeholder(placeholder) => output.extend(substitute(*placeholder)?),
+        }
+    }
+    Ok(())
+}
+
+/// Wraps an expression that is not atomic in parentheses.
+///
+/// The replacement is inserted into the syntax tree in place of the literal, but the tree is
+/// printed without regard for precedence. Without parentheses, `2 * t` with the replacement
+/// `T::zero() + T::from(literal)` would become `T::zero() + T::from(2) * t`. The parentheses
+/// are spanned as macro output, so that they are not linted as unnecessary where the
+/// replacement would have been fine on its own.
+fn parenthesized(expr: Expr) -> Expr {
+    match expr {
+        Expr::Lit(_)
+        | Expr::Path(_)
+        | Expr::Call(_)
+        | Expr::MethodCall(_)
+        | Expr::Macro(_)
+        | Expr::Field(_)
+        | Expr::Index(_)
+        | Expr::Paren(_)
+        | Expr::Group(_)
+        | Expr::Tuple(_)
+        | Expr::Array(_)
+        | Expr::Block(_) => expr,
+        expr => Expr::Paren(ExprParen {
+            attrs: Vec::new(),
+            paren_token: syn::token::Paren(Span::call_site()),
+            expr: Box::new(expr),
+        }),
+    }
+}
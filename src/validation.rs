@@ -0,0 +1,170 @@
+//! Detection of common mistakes in replacement expressions.
+//!
+//! The replacement expression is only type checked once it has been inserted in place of each
+//! literal, so a mistake in it is reported by the compiler at every literal, with messages that
+//! do not mention the attribute. The mistakes that can be recognized from the syntax alone are
+//! therefore reported once at the attribute instead.
+
+use crate::diagnostics::Warning;
+use crate::routing::Replacement;
+use crate::template::Placeholder;
+use proc_macro2::{TokenStream, TokenTree};
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{Block, Expr, Macro, Stmt, TypePath};
+
+/// Checks the replacement for common mistakes, in which `placeholder` is the name of the literal.
+///
+/// Placeholders in types or in the bodies of macros are never replaced, and are reported as
+/// errors. A replacement expression that discards the literal, as in `{ literal; }`, is reported
+/// as a warning. With `strict`, a single replacement expression without any placeholder is an
+/// error, unless `constant` is set because all replaced literals have the same value (e.g. for
+/// `replace_zero_literals`). Such replacements are otherwise intended, as in
+/// `replace_int_literals(N)`. The arms of a `match_value` replacement may be constants, such as
+/// `"small"`, so they may omit the placeholder.
+pub fn validate_replacement(
+    replacement: &Replacement,
+    placeholder: &str,
+    constant: bool,
+    strict: bool,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), syn::Error> {
+    for expr in replacement.expressions() {
+        let mut finder = PlaceholderFinder {
+            placeholder,
+            occurrences: 0,
+            error: None,
+        };
+        finder.visit_expr(expr);
+        if let Some(error) = finder.error {
+            return Err(error);
+        }
+        if finder.occurrences > 0 && is_unit(expr) {
+            warnings.push(Warning::new(
+                expr.span(),
+                "the replacement expression always evaluates to `()`, \
+                 so the replaced literals have no value",
+            ));
+        } else if finder.occurrences == 0
+            && strict
+            && !constant
+            && matches!(replacement, Replacement::Single(_))
+        {
+            return Err(syn::Error::new(
+                expr.span(),
+                format!(
+                    "the replacement expression does not contain the placeholder `{}`, \
+                     so every literal is replaced with the same value",
+                    placeholder
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Counts the placeholders in expression positions, and reports the first one elsewhere.
+struct PlaceholderFinder<'a> {
+    placeholder: &'a str,
+    occurrences: usize,
+    error: Option<syn::Error>,
+}
+
+impl<'a> PlaceholderFinder<'a> {
+    fn is_placeholder(&self, ident: &proc_macro2::Ident) -> bool {
+        Placeholder::from_name(&ident.to_string(), self.placeholder).is_some()
+    }
+
+    fn report(&mut self, error: syn::Error) {
+        if self.error.is_none() {
+            self.error = Some(error);
+        }
+    }
+}
+
+impl<'a, 'ast> Visit<'ast> for PlaceholderFinder<'a> {
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        // Like the template, only the last segment of a path is considered a placeholder
+        if let Expr::Path(path_expr) = expr {
+            if let Some(segment) = path_expr.path.segments.last() {
+                if self.is_placeholder(&segment.ident) {
+                    self.occurrences += 1;
+                    return;
+                }
+            }
+        }
+        visit::visit_expr(self, expr);
+    }
+
+    fn visit_type_path(&mut self, type_path: &'ast TypePath) {
+        if let Some(segment) = type_path
+            .path
+            .segments
+            .iter()
+            .find(|segment| self.is_placeholder(&segment.ident))
+        {
+            let message = format!(
+                "the placeholder `{}` cannot be used as a type, since it is replaced with a value",
+                segment.ident
+            );
+            self.report(syn::Error::new(segment.ident.span(), message));
+        }
+        visit::visit_type_path(self, type_path);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        if let Some(ident) = find_placeholder_token(mac.tokens.clone(), self) {
+            let message = format!(
+                "the placeholder `{}` is not replaced in the body of a macro. \
+                 Consider binding it first, as in `{{ let value = {}; {}!(..., value) }}`",
+                ident,
+                ident,
+                mac.path
+                    .segments
+                    .last()
+                    .map(|segment| segment.ident.to_string())
+                    .unwrap_or_default()
+            );
+            self.report(syn::Error::new(ident.span(), message));
+        }
+    }
+}
+
+/// Finds an identifier token that names a placeholder, at any depth.
+fn find_placeholder_token(
+    tokens: TokenStream,
+    finder: &PlaceholderFinder,
+) -> Option<proc_macro2::Ident> {
+    tokens.into_iter().find_map(|token| match token {
+        TokenTree::Ident(ident) if finder.is_placeholder(&ident) => Some(ident),
+        TokenTree::Group(group) => find_placeholder_token(group.stream(), finder),
+        _ => None,
+    })
+}
+
+/// Returns whether the expression evaluates to `()` regardless of the literal, such as an
+/// assignment or a block that ends with a statement.
+fn is_unit(expr: &Expr) -> bool {
+    match expr {
+        Expr::Tuple(tuple) => tuple.elems.is_empty(),
+        Expr::Assign(_) | Expr::AssignOp(_) | Expr::While(_) | Expr::ForLoop(_) => true,
+        Expr::Paren(paren) => is_unit(&paren.expr),
+        Expr::Block(block) => is_unit_block(&block.block),
+        Expr::Unsafe(unsafe_expr) => is_unit_block(&unsafe_expr.block),
+        _ => false,
+    }
+}
+
+/// Returns whether the block evaluates to `()`, e.g. `{ literal; }`.
+fn is_unit_block(block: &Block) -> bool {
+    match block.stmts.last() {
+        None | Some(Stmt::Local(_)) | Some(Stmt::Item(_)) => true,
+        Some(Stmt::Expr(expr)) => is_unit(expr),
+        // The block diverges if its last statement does, as in `{ return literal; }` or
+        // `{ panic!(...); }`, so that the replacement may still be intended
+        Some(Stmt::Semi(expr, _)) => !matches!(
+            expr,
+            Expr::Return(_) | Expr::Break(_) | Expr::Continue(_) | Expr::Macro(_)
+        ),
+    }
+}
@@ -0,0 +1,85 @@
+//! Parsing of expressions that are kept as verbatim tokens in the syntax tree.
+//!
+//! Syntax that is newer than the supported version of `syn`, such as `let ... else` and inline
+//! `const` blocks, is not parsed into the syntax tree but kept as an `Expr::Verbatim`. The
+//! constructs recognized here are parsed into their parts, so that the expressions in them can
+//! be visited while their patterns are left untouched.
+
+use proc_macro2::{Spacing, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::parse::{Parse, ParseStream};
+use syn::{Block, Expr, Token};
+
+/// An expression of syntax that is kept as verbatim tokens.
+pub enum VerbatimExpr {
+    /// `let pattern = expr else { ... }`, without the terminating semicolon
+    LetElse {
+        let_token: Token![let],
+        pattern: TokenStream,
+        eq_token: Token![=],
+        expr: Box<Expr>,
+        else_token: Token![else],
+        block: Block,
+    },
+    /// `const { ... }`
+    ConstBlock {
+        const_token: Token![const],
+        block: Block,
+    },
+}
+
+impl Parse for VerbatimExpr {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![const]) {
+            return Ok(VerbatimExpr::ConstBlock {
+                const_token: input.parse()?,
+                block: input.parse()?,
+            });
+        }
+        let let_token = input.parse()?;
+        // The pattern extends to the first `=` that is not part of `==`, `=>` or `..=`
+        let mut pattern = TokenStream::new();
+        let mut after_dots = false;
+        while !(input.peek(Token![=]) && !input.peek(Token![==]) && !input.peek(Token![=>]))
+            || after_dots
+        {
+            let token: TokenTree = input.parse()?;
+            after_dots = matches!(&token, TokenTree::Punct(punct) if punct.as_char() == '.' && punct.spacing() == Spacing::Joint);
+            pattern.extend(Some(token));
+        }
+        Ok(VerbatimExpr::LetElse {
+            let_token,
+            pattern,
+            eq_token: input.parse()?,
+            expr: input.parse()?,
+            else_token: input.parse()?,
+            block: input.parse()?,
+        })
+    }
+}
+
+impl ToTokens for VerbatimExpr {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            VerbatimExpr::LetElse {
+                let_token,
+                pattern,
+                eq_token,
+                expr,
+                else_token,
+                block,
+            } => {
+                let_token.to_tokens(tokens);
+                pattern.to_tokens(tokens);
+                eq_token.to_tokens(tokens);
+                expr.to_tokens(tokens);
+                else_token.to_tokens(tokens);
+                block.to_tokens(tokens);
+            }
+            VerbatimExpr::ConstBlock { const_token, block } => {
+                const_token.to_tokens(tokens);
+                block.to_tokens(tokens);
+            }
+        }
+    }
+}
@@ -0,0 +1,27 @@
+//! Tests for syntax that is only available on nightly Rust. Run with
+//! `RUSTFLAGS="--cfg numeric_literals_nightly" cargo +nightly test --test nightly`.
+//!
+//! The tests are in submodules, since nightly syntax must not even be parsed on stable.
+#![cfg_attr(
+    numeric_literals_nightly,
+    feature(
+        coroutine_trait,
+        coroutines,
+        custom_inner_attributes,
+        proc_macro_hygiene,
+        stmt_expr_attributes,
+        try_blocks
+    )
+)]
+
+#[cfg(numeric_literals_nightly)]
+#[path = "nightly/coroutines.rs"]
+mod coroutines;
+
+#[cfg(numeric_literals_nightly)]
+#[path = "nightly/inner_attributes.rs"]
+mod inner_attributes;
+
+#[cfg(numeric_literals_nightly)]
+#[path = "nightly/try_blocks.rs"]
+mod try_blocks;
@@ -0,0 +1,28 @@
+use numeric_literals::replace_float_literals;
+use std::ops::{Coroutine, CoroutineState};
+use std::pin::Pin;
+
+#[test]
+fn converts_yielded_values() {
+    #[replace_float_literals(literal * 2.0)]
+    fn yielded() -> Vec<f64> {
+        let mut coroutine = #[coroutine]
+        || {
+            yield 1.5;
+            yield 0.25 + 1.0;
+            3.0
+        };
+        let mut values = Vec::new();
+        loop {
+            match Pin::new(&mut coroutine).resume(()) {
+                CoroutineState::Yielded(value) => values.push(value),
+                CoroutineState::Complete(value) => {
+                    values.push(value);
+                    return values;
+                }
+            }
+        }
+    }
+
+    assert_eq!(yielded(), vec![3.0, 2.5, 6.0]);
+}
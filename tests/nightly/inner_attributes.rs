@@ -0,0 +1,27 @@
+mod defaults {
+    #![numeric_literals::replace_numeric_literals(literal as f64 * 2.0)]
+
+    pub fn doubled() -> f64 {
+        1.5 + 3
+    }
+
+    // Items with their own attribute are only converted by that attribute
+    #[numeric_literals::replace_float_literals(literal as f32)]
+    pub fn quarter() -> f32 {
+        0.25
+    }
+}
+
+#[test]
+fn applies_inner_attributes_of_modules() {
+    assert_eq!(defaults::doubled(), 9.0);
+    assert_eq!(defaults::quarter(), 0.25);
+}
+
+#[path = "inner_attributes/file_module.rs"]
+mod file_module;
+
+#[test]
+fn applies_inner_attributes_of_module_files() {
+    assert_eq!(file_module::doubled(), 9.0);
+}
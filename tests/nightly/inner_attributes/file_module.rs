@@ -0,0 +1,5 @@
+#![numeric_literals::replace_numeric_literals(literal as f64 * 2.0)]
+
+pub fn doubled() -> f64 {
+    1.5 + 3
+}
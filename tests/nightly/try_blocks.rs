@@ -0,0 +1,13 @@
+use numeric_literals::replace_float_literals;
+
+#[test]
+fn converts_try_blocks() {
+    #[replace_float_literals(literal * 2.0)]
+    fn sum(x: Option<f64>) -> Option<f64> {
+        let result: Option<f64> = try { x? + 1.0 + 2.0 };
+        result
+    }
+
+    assert_eq!(sum(Some(0.5)), Some(6.5));
+    assert_eq!(sum(None), None);
+}
@@ -1,7 +1,14 @@
+// Casting literals and asserting on literal comparisons is exactly what these tests exercise
+#![allow(clippy::unnecessary_cast, clippy::assertions_on_constants)]
+
 extern crate numeric_literals;
 
-use numeric_literals::{replace_float_literals, replace_int_literals, replace_numeric_literals};
-use std::ops::Add;
+use num::Float;
+use numeric_literals::{
+    replace_float_literals, replace_int_literals, replace_nonzero_literals,
+    replace_numeric_literals, replace_one_literals, replace_uint_literals, replace_zero_literals,
+};
+use std::ops::{Add, Neg};
 
 #[test]
 fn leaves_string_untouched() {
@@ -159,6 +166,63 @@ fn converts_vec_trailing_comma() {
     assert_eq!(gen_f64_vec(), vec![1.5, 0.5, 4.5]);
 }
 
+#[test]
+fn converts_nested_vec() {
+    #[replace_float_literals(literal as i32)]
+    fn gen_repeated() -> Vec<Vec<Vec<i32>>> {
+        vec![vec![vec![2.5; 2]; 2]; 2]
+    }
+
+    assert_eq!(
+        gen_repeated(),
+        vec![vec![vec![2, 2], vec![2, 2]], vec![vec![2, 2], vec![2, 2]]]
+    );
+
+    #[replace_float_literals(literal as i32)]
+    fn gen_listed() -> Vec<Vec<Vec<i32>>> {
+        vec![vec![vec![1.5, 2.5], vec![3.5]], vec![vec![4.5]]]
+    }
+
+    assert_eq!(gen_listed(), vec![vec![vec![1, 2], vec![3]], vec![vec![4]]]);
+
+    #[replace_float_literals(literal as i32)]
+    fn gen_mixed() -> Vec<Vec<Vec<i32>>> {
+        vec![vec![vec![1.5; 2], vec![2.5, 3.5]]; 2]
+    }
+
+    assert_eq!(
+        gen_mixed(),
+        vec![vec![vec![1, 1], vec![2, 3]], vec![vec![1, 1], vec![2, 3]]]
+    );
+}
+
+#[test]
+fn converts_user_macros_nested_in_macros() {
+    macro_rules! identity {
+        ($e:expr) => {
+            $e
+        };
+    }
+
+    #[replace_float_literals(literal as i32)]
+    fn nested() -> Vec<i32> {
+        vec![identity!(1.5), 2.5, identity!(identity!(vec![3.5])[0])]
+    }
+
+    assert_eq!(nested(), vec![1, 2, 3]);
+}
+
+#[test]
+fn converts_vec_nested_in_assert_eq() {
+    #[replace_float_literals(literal as i32)]
+    fn truncated() -> Vec<i32> {
+        assert_eq!(vec![vec![1.5; 2]; 1], vec![vec![1.25, 1.75]]);
+        vec![1.5, 2.5]
+    }
+
+    assert_eq!(truncated(), vec![1, 2]);
+}
+
 #[test]
 fn converts_assert_eq_floats_to_i32() {
     #[replace_float_literals(literal as i32)]
@@ -171,6 +235,26 @@ fn converts_assert_eq_floats_to_i32() {
     assert_eq_test();
 }
 
+#[test]
+fn converts_assert_ne_floats_to_i32() {
+    #[replace_float_literals(literal as i32)]
+    fn assert_ne_test() {
+        assert_ne!(1.5, 2);
+        assert_ne!(2.6, 3);
+    }
+
+    // The message is left as it is, while its arguments are converted
+    #[replace_float_literals(literal as i32)]
+    fn assert_ne_message_test() -> String {
+        assert_ne!(1.5, 2, "1.5 and 2 differ");
+        assert_ne!(10.99, 11, "{} and {} differ", 10.99, 11);
+        format!("{}", 10.99)
+    }
+
+    assert_ne_test();
+    assert_eq!(assert_ne_message_test(), "10");
+}
+
 #[test]
 fn converts_assert_floats_to_i32() {
     #[replace_float_literals(literal as i32)]
@@ -315,4 +399,2252 @@ fn converts_suffixed_ints() {
     test_int();
     test_float();
     test_mixed();
+
+    // syn parses `20f64` as an integer literal with a float suffix, which is still a float literal
+    #[replace_int_literals(literal as f64 * 2.0)]
+    fn int_replaced() -> (f64, f64, f64) {
+        (20f64, 21_f32 as f64, 3)
+    }
+
+    #[replace_float_literals(literal * 2.0)]
+    fn float_replaced() -> (f64, f32, i32) {
+        (20f64, 21_f32, 3)
+    }
+
+    assert_eq!(int_replaced(), (20.0, 21.0, 6.0));
+    assert_eq!(float_replaced(), (40.0, 42.0, 3));
+}
+
+/// Minimal executor for driving the futures produced by the async tests below to completion.
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+    fn noop_raw_waker() -> RawWaker {
+        fn clone(_: *const ()) -> RawWaker {
+            noop_raw_waker()
+        }
+        fn noop(_: *const ()) {}
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+        RawWaker::new(std::ptr::null(), &VTABLE)
+    }
+
+    let waker = unsafe { Waker::from_raw(noop_raw_waker()) };
+    let mut context = Context::from_waker(&waker);
+    let mut future = Box::pin(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+#[test]
+fn converts_async_fn_body() {
+    use num::FromPrimitive;
+
+    #[replace_float_literals(T::from_f64(literal).unwrap())]
+    async fn compute<T>() -> T
+    where
+        T: FromPrimitive + Add<T, Output = T>,
+    {
+        1.5 + 2.5
+    }
+
+    assert_eq!(block_on(compute::<f32>()), 4.0);
+    assert_eq!(block_on(compute::<f64>()), 4.0);
+}
+
+#[test]
+fn converts_async_fn_await_operands() {
+    #[replace_float_literals(literal as i32)]
+    async fn compute() -> i32 {
+        std::future::ready(1.5).await + async { 2.5 }.await
+    }
+
+    assert_eq!(block_on(compute()), 3);
+}
+
+#[test]
+fn converts_async_move_block_in_async_fn() {
+    #[replace_float_literals(literal as i32)]
+    async fn compute(x: i32) -> i32 {
+        async move { x * 3.0 }.await
+    }
+
+    assert_eq!(block_on(compute(2)), 6);
+}
+
+#[test]
+fn converts_literals_around_await_and_in_async_closures() {
+    use num::FromPrimitive;
+
+    async fn load<T>(value: T) -> T {
+        value
+    }
+
+    // Literals are replaced on both sides of the `.await` in every iteration
+    #[replace_float_literals(T::from_f64(literal).unwrap())]
+    async fn iterate<T>(steps: usize) -> T
+    where
+        T: FromPrimitive + Float,
+    {
+        let mut x = 0.0;
+        for _ in 0..steps {
+            x = 0.5 * load(x + 1.0).await + 0.25;
+        }
+        x
+    }
+
+    // The body of an async closure, which is run like a spawned task
+    #[replace_float_literals(T::from_f64(literal).unwrap())]
+    fn spawn_scaled<T>(value: T) -> T
+    where
+        T: FromPrimitive + Float,
+    {
+        let task = async move |x: T| load(x * 2.0).await + 1.5;
+        block_on(task(value))
+    }
+
+    assert_eq!(block_on(iterate::<f64>(1)), 0.75);
+    assert_eq!(block_on(iterate::<f32>(2)), 1.125);
+    assert_eq!(spawn_scaled(2.0f64), 5.5);
+}
+
+#[test]
+#[allow(clippy::manual_async_fn)]
+fn converts_async_blocks_returned_from_fn() {
+    use num::FromPrimitive;
+    use std::future::Future;
+    use std::ops::Mul;
+
+    #[replace_float_literals(T::from_f64(literal).unwrap())]
+    fn sum<T>() -> impl Future<Output = T>
+    where
+        T: FromPrimitive + Add<T, Output = T>,
+    {
+        async { 1.5 + 2.5 }
+    }
+
+    assert_eq!(block_on(sum::<f32>()), 4.0);
+
+    // Only the literal is replaced, not the captured variable
+    #[replace_float_literals(T::from_f64(literal).unwrap())]
+    fn triple<T>(x: T) -> impl Future<Output = T>
+    where
+        T: FromPrimitive + Mul<T, Output = T>,
+    {
+        async move { 3.0 * x }
+    }
+
+    assert_eq!(block_on(triple(2.0f64)), 6.0);
+}
+
+#[test]
+#[rustfmt::skip]
+fn converts_scaled_literals() {
+    #[derive(Debug, PartialEq)]
+    struct Money(i64);
+
+    impl Money {
+        fn from_minor_units(units: i64) -> Self {
+            Money(units)
+        }
+    }
+
+    #[replace_float_literals(Money::from_minor_units(literal_scaled), scale = 4)]
+    fn gen_money() -> Vec<Money> {
+        vec![1.25, 2., 0.0001, 1e-2, 1_000.5, 12.5e2, 0.0]
+    }
+
+    assert_eq!(
+        gen_money(),
+        vec![
+            Money(12500),
+            Money(20000),
+            Money(1),
+            Money(100),
+            Money(10005000),
+            Money(12500000),
+            Money(0),
+        ]
+    );
+
+    #[replace_numeric_literals(Money::from_minor_units(literal_scaled), scale = 2)]
+    fn gen_mixed() -> (Money, Money) {
+        (3, 0.5)
+    }
+
+    assert_eq!(gen_mixed(), (Money(300), Money(50)));
+}
+
+#[test]
+fn converts_const_fn_body() {
+    #[replace_int_literals(literal as f64)]
+    const fn gen() -> f64 {
+        3
+    }
+
+    const VALUE: f64 = gen();
+    assert_eq!(VALUE, 3.0);
+}
+
+#[test]
+fn converts_const_fn_body_with_const_safe_call() {
+    #[replace_int_literals(i64::pow(literal, 2), const_safe = true)]
+    const fn gen() -> i64 {
+        3
+    }
+
+    const VALUE: i64 = gen();
+    assert_eq!(VALUE, 9);
+}
+
+#[test]
+#[allow(deprecated)]
+fn converts_const_fn_body_with_unchecked_call() {
+    // Warns that the replacement may not be const-evaluable, but still compiles
+    #[replace_int_literals(i64::pow(literal, 2))]
+    const fn gen() -> i64 {
+        3
+    }
+
+    const VALUE: i64 = gen();
+    assert_eq!(VALUE, 9);
+}
+
+#[test]
+fn converts_associated_consts() {
+    struct Tolerance;
+
+    #[replace_float_literals(literal as f32)]
+    impl Tolerance {
+        const EPSILON: f32 = 0.001;
+        const SQUARED: f32 = 0.001 * 0.001;
+    }
+
+    #[replace_int_literals(i64::pow(literal, 2), const_safe = true)]
+    trait Squares {
+        const NINE: i64 = 3;
+    }
+
+    impl Squares for Tolerance {}
+
+    assert_eq!(Tolerance::EPSILON, 0.001);
+    assert_eq!(Tolerance::SQUARED, 0.001f32 * 0.001);
+    assert_eq!(Tolerance::NINE, 9);
+}
+
+#[test]
+#[allow(deprecated)]
+fn converts_associated_consts_with_unchecked_call() {
+    // Warns that the replacement may not be const-evaluable, but still compiles
+    struct Squares;
+
+    #[replace_int_literals(i64::pow(literal, 2))]
+    impl Squares {
+        const NINE: i64 = 3;
+    }
+
+    assert_eq!(Squares::NINE, 9);
+}
+
+#[test]
+fn converts_trait_provided_methods() {
+    use num::Float;
+
+    #[replace_numeric_literals(T::from(literal).unwrap())]
+    trait Weighted<T: Float> {
+        fn weight(&self) -> T;
+
+        fn default_weight(&self) -> T {
+            0.5
+        }
+
+        fn scaled_weight(&self) -> T {
+            self.weight() * 2
+        }
+    }
+
+    // The replacement is resolved in the scope of each method, so `T` may also be a method parameter
+    #[replace_numeric_literals(T::from(literal).unwrap())]
+    trait Halve {
+        fn halve<T: Float>(&self, value: T) -> T {
+            value / 2
+        }
+    }
+
+    struct Unit;
+
+    impl Weighted<f64> for Unit {
+        fn weight(&self) -> f64 {
+            1.5
+        }
+    }
+
+    assert_eq!(Unit.default_weight(), 0.5);
+    assert_eq!(Unit.scaled_weight(), 3.0);
+
+    impl Halve for Unit {}
+
+    assert_eq!(Unit.halve(3.0f32), 1.5);
+    assert_eq!(Unit.halve(3.0f64), 1.5);
+}
+
+#[test]
+fn converts_module_items() {
+    #[replace_numeric_literals(T::from(literal))]
+    mod generic {
+        use std::ops::Add;
+
+        pub fn three<T: From<i8>>() -> T {
+            3
+        }
+
+        pub fn five<T: From<i8> + Add<T, Output = T>>() -> T {
+            2 + 3
+        }
+
+        pub mod nested {
+            pub fn seven<T: From<i8>>() -> T {
+                7
+            }
+        }
+    }
+
+    assert_eq!(generic::three::<f64>(), 3.0);
+    assert_eq!(generic::five::<i32>(), 5);
+    assert_eq!(generic::nested::seven::<i64>(), 7);
+}
+
+#[test]
+fn accepts_literals_exactly_representable_as_f32() {
+    #[replace_float_literals(literal as f32, deny_inexact_f32 = true)]
+    fn gen() -> Vec<f32> {
+        vec![0.5, 3.0, 1.25, 2., 16777216.0, 0.0, 0.75e1]
+    }
+
+    assert_eq!(gen(), vec![0.5, 3.0, 1.25, 2.0, 16777216.0, 0.0, 7.5]);
+}
+
+#[test]
+#[allow(deprecated)]
+fn warns_about_literals_inexact_as_f32() {
+    // Warns about `0.1`, but still compiles
+    #[replace_float_literals(literal as f32, warn_inexact_f32 = true)]
+    fn gen() -> f32 {
+        0.1
+    }
+
+    assert_eq!(gen(), 0.1);
+}
+
+#[test]
+fn converts_with_literal_kind() {
+    #[replace_numeric_literals(if literal_kind == "int" { literal as f64 * 10.0 } else { literal as f64 })]
+    fn gen() -> Vec<f64> {
+        vec![3, 2.5, 1_u8, 4f32]
+    }
+
+    assert_eq!(gen(), vec![30.0, 2.5, 10.0, 4.0]);
+
+    #[replace_numeric_literals(literal_kind)]
+    fn gen_kinds() -> Vec<&'static str> {
+        vec![1, 1.0]
+    }
+
+    assert_eq!(gen_kinds(), vec!["int", "float"]);
+}
+
+#[test]
+fn accepts_exact_literals_with_warn_inexact() {
+    #[replace_float_literals(literal, warn_inexact = true)]
+    fn gen() -> Vec<f64> {
+        vec![0.5, 3.0, 1.25, 2., 1e22, 0.75e1, 2.5f32 as f64]
+    }
+
+    assert_eq!(gen(), vec![0.5, 3.0, 1.25, 2.0, 1e22, 7.5, 2.5]);
+}
+
+#[test]
+#[allow(deprecated)]
+fn warns_about_inexact_literals() {
+    // Warns about both literals, but still compiles
+    #[replace_float_literals(literal, warn_inexact = true)]
+    fn gen() -> (f64, f32) {
+        (0.1, 0.1f32)
+    }
+
+    assert_eq!(gen(), (0.1, 0.1));
+}
+
+#[test]
+fn accepts_integer_literals_within_range() {
+    #[replace_numeric_literals(T::from(literal), fits = i8)]
+    fn gen<T: From<i8> + Neg<Output = T>>() -> Vec<T> {
+        vec![-127, 127, 0]
+    }
+
+    assert_eq!(gen::<i32>(), vec![-127, 127, 0]);
+
+    #[replace_int_literals(literal as u8, min = 0, max = 200)]
+    fn gen_bounded() -> Vec<u8> {
+        vec![0, 100, 200]
+    }
+
+    assert_eq!(gen_bounded(), vec![0, 100, 200]);
+
+    #[replace_int_literals(literal as i64, min = -10, max = -1, fits = i8)]
+    fn gen_negative() -> Vec<i64> {
+        vec![-10, -1]
+    }
+
+    assert_eq!(gen_negative(), vec![-10, -1]);
+}
+
+#[test]
+fn converts_with_literal_index() {
+    #[replace_numeric_literals((literal as f64, literal_index))]
+    fn gen() -> Vec<(f64, usize)> {
+        vec![10, 2.5, 7]
+    }
+
+    assert_eq!(gen(), vec![(10.0, 0), (2.5, 1), (7.0, 2)]);
+
+    // The index only counts replaced literals, and starts over for each decorated item
+    #[replace_float_literals(literal_index)]
+    fn gen_indices() -> (usize, i32, usize) {
+        (1.5, 2, 3.5)
+    }
+
+    assert_eq!(gen_indices(), (0, 2, 1));
+}
+
+#[test]
+fn ignores_suffixed_literals() {
+    #[replace_numeric_literals(literal as i64, ignore_suffixed = true)]
+    fn gen() -> (i64, u8, f32) {
+        (3, 200u8, 1.5f32)
+    }
+
+    assert_eq!(gen(), (3, 200u8, 1.5f32));
+}
+
+#[test]
+fn converts_with_literal_text() {
+    #[replace_numeric_literals(literal_text)]
+    fn gen() -> Vec<&'static str> {
+        vec![1.618, 42, 1e-3, 0x1F, 2.5f32]
+    }
+
+    assert_eq!(gen(), vec!["1.618", "42", "1e-3", "0x1F", "2.5f32"]);
+
+    #[replace_float_literals(literal_text.parse::<f64>().unwrap())]
+    fn parsed() -> f64 {
+        0.1
+    }
+
+    assert_eq!(parsed(), 0.1);
+}
+
+#[test]
+fn converts_with_literal_float() {
+    use num::FromPrimitive;
+
+    #[replace_numeric_literals(T::from_f64(literal_float).unwrap())]
+    fn gen<T: FromPrimitive>() -> Vec<T> {
+        vec![3, 2.5, 0x10, 9007199254740992, 1u8]
+    }
+
+    assert_eq!(gen::<f32>(), vec![3.0, 2.5, 16.0, 9007199254740992.0, 1.0]);
+}
+
+#[test]
+fn converts_with_literal_location() {
+    #[replace_int_literals((literal_file, literal_line, literal_column))]
+    fn location() -> (&'static str, u32, u32) {
+        #[rustfmt::skip]
+        let location =    42;
+        location
+    }
+
+    assert_eq!(location(), (file!(), line!() - 4, 27));
+}
+
+#[test]
+fn routes_by_literal_value() {
+    #[replace_numeric_literals(match_value {
+        0.0..1.0 => "small",
+        1.0..=10 | 100 => "medium",
+        _ => "large",
+    })]
+    fn gen() -> Vec<&'static str> {
+        vec![0.5, 1, 10, 10.5, 100, 1e3]
+    }
+
+    assert_eq!(
+        gen(),
+        vec!["small", "medium", "medium", "large", "medium", "large"]
+    );
+
+    // The negation is not part of the literal, so `-0.25` is routed by the value `0.25`
+    #[replace_float_literals(match_value {
+        0.0..=0.5 => literal as f32 as f64,
+        _ => literal,
+    })]
+    fn values() -> (f64, f64, f64) {
+        (-0.25, 0.1, 2.1)
+    }
+
+    assert_eq!(values(), (-0.25, 0.1f32 as f64, 2.1));
+}
+
+#[test]
+fn routes_by_match_on_literal() {
+    // The arms are selected at expansion time, and match both integer and float spellings
+    #[replace_numeric_literals(match literal {
+        0 => T::zero(),
+        1.0 => T::one(),
+        _ => T::from(literal).unwrap(),
+    })]
+    fn gen<T: Float>() -> Vec<T> {
+        vec![0, 0.0, 1, 1.0, 2.5]
+    }
+
+    assert_eq!(gen::<f64>(), vec![0.0, 0.0, 1.0, 1.0, 2.5]);
+
+    // Only the selected arms are emitted, so the other arms need not compile, unlike a runtime match
+    #[replace_float_literals(match literal {
+        0.0 => T::default(),
+        _ => T::from(literal),
+    })]
+    fn zero<T: Default>() -> T {
+        0.0
+    }
+
+    assert_eq!(zero::<u8>(), 0);
+}
+
+#[test]
+fn keeps_runtime_match_on_literal() {
+    // Arms with bindings or guards cannot be decided at expansion time, so the match is kept
+    #[replace_float_literals(match literal { x => x as f32 })]
+    fn narrowed() -> f32 {
+        2.5
+    }
+
+    #[replace_int_literals(match literal { x if x > 10 => 10, x => x })]
+    fn clamped() -> [i32; 2] {
+        [5, 20]
+    }
+
+    assert_eq!(narrowed(), 2.5f32);
+    assert_eq!(clamped(), [5, 10]);
+}
+
+#[test]
+fn skips_unparseable_macro_bodies() {
+    macro_rules! sum_fields {
+        ($($field:ident : $value:expr),*) => { 0 $(+ $value)* };
+    }
+
+    // The body of `sum_fields!` is not an expression, so its literals are left untouched
+    #[replace_int_literals(literal * 10)]
+    fn gen() -> (i32, i32) {
+        (sum_fields!(a: 1, b: 2), 3)
+    }
+
+    assert_eq!(gen(), (3, 30));
+}
+
+#[test]
+#[allow(deprecated)]
+fn warns_about_unparseable_macro_bodies_with_strict() {
+    macro_rules! sum_fields {
+        ($($field:ident : $value:expr),*) => { 0 $(+ $value)* };
+    }
+
+    // The macro is still skipped, but reported at the invocation
+    #[replace_int_literals(literal * 10, strict = true)]
+    fn gen() -> (i32, i32) {
+        (sum_fields!(a: 1, b: 2), 3)
+    }
+
+    assert_eq!(gen(), (3, 30));
+}
+
+#[test]
+fn rewrites_only_changed_macro_arguments() {
+    #[replace_float_literals(literal * 2.0)]
+    fn gen() -> Vec<f64> {
+        vec![
+            1.0,
+            [2.0, 3.0].iter().sum::<f64>(),
+            Vec::<f64>::with_capacity(1).len() as f64,
+        ]
+    }
+
+    assert_eq!(gen(), vec![2.0, 10.0, 0.0]);
+}
+
+#[test]
+fn keeps_spans_of_unchanged_macro_arguments() {
+    macro_rules! text {
+        ($($t:tt)*) => { stringify!($($t)*) };
+    }
+
+    // The first argument contains no literal, so it is emitted with the tokens and spans it was
+    // written with. Re-emitting it from its parsed form would split `<-` into `< -`.
+    #[replace_float_literals(literal as u32)]
+    fn locations() -> Vec<(u32, u32, &'static str)> {
+        vec![(line!(), column!(), text!(a<-b)), (line!(), 2.0, "")]
+    }
+
+    let line = line!() - 3;
+    assert_eq!(locations(), vec![(line, 24, "a <- b"), (line, 2, "")]);
+}
+
+#[test]
+fn replaces_zero_and_nonzero_literals_separately() {
+    #[replace_zero_literals(T::zero())]
+    #[replace_nonzero_literals(T::from(literal).unwrap())]
+    fn gen<T: Float>() -> Vec<T> {
+        vec![0.0, 1.5, 0, -2, 0e5, 0x0]
+    }
+
+    assert_eq!(gen::<f64>(), vec![0.0, 1.5, 0.0, -2.0, 0.0, 0.0]);
+
+    #[replace_zero_literals("zero")]
+    fn gen_zeros() -> (&'static str, i32, &'static str) {
+        (0.0, 1, 0_u8)
+    }
+
+    assert_eq!(gen_zeros(), ("zero", 1, "zero"));
+}
+
+#[test]
+fn replaces_unsigned_literals_only() {
+    // Only literals with an unsigned suffix are replaced, signed and unsuffixed ones remain
+    #[replace_uint_literals(Mask(literal as u128))]
+    fn gen() -> (Mask, Mask, Mask, Mask, i32, u8, i64, f64) {
+        (0xFFu8, 256u16, 65535_u32, 3usize, -1i32, 7, 20i64, 1.5)
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Mask(u128);
+
+    assert_eq!(
+        gen(),
+        (Mask(255), Mask(256), Mask(65535), Mask(3), -1, 7, 20, 1.5)
+    );
+}
+
+#[test]
+fn replaces_literal_tokens_in_unparseable_macro_bodies() {
+    macro_rules! sum_fields {
+        ($($(#[$attr:meta])* $field:ident : $value:expr),*) => { [$($value),*].iter().sum::<i32>() };
+    }
+
+    // Literals in attributes are left untouched
+    #[replace_int_literals((literal as i32).pow(2), macro_fallback = tokens)]
+    fn gen() -> i32 {
+        sum_fields!(#[weight(8)] a: 4, b: 5)
+    }
+
+    assert_eq!(gen(), 41);
+}
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn leaves_asm_untouched() {
+    // The immediate operand would not compile if it were replaced, even by the token fallback
+    #[replace_int_literals(String::from(literal), macro_fallback = tokens)]
+    fn add_five(x: u64) -> u64 {
+        let y: u64;
+        unsafe { std::arch::asm!("lea {0}, [{1} + {2}]", out(reg) y, in(reg) x, const 5) };
+        y
+    }
+
+    assert_eq!(add_five(2), 7);
+}
+
+#[test]
+fn replaces_one_literals() {
+    #[replace_zero_literals("zero")]
+    #[replace_one_literals("one")]
+    #[replace_nonzero_literals("other")]
+    fn gen() -> Vec<&'static str> {
+        vec![0, 1, 1.0, 10e-1, 0x1, 1.5, 11]
+    }
+
+    assert_eq!(
+        gen(),
+        vec!["zero", "one", "one", "one", "one", "other", "other"]
+    );
+}
+
+#[test]
+fn leaves_patterns_in_matches_untouched() {
+    // The range pattern is not replaced, while the scrutinee and the guard are
+    #[replace_float_literals(literal * 2.0)]
+    fn in_range(x: f64) -> bool {
+        matches!(x * 1.0, 1.0..=2.0 if x < 0.75)
+    }
+
+    assert!(in_range(0.5));
+    assert!(in_range(0.8));
+    assert!(!in_range(0.4));
+    assert!(!in_range(1.2));
+
+    // Literals in or-patterns are left untouched as well
+    #[replace_int_literals(literal + 1)]
+    fn in_alternatives(x: i32) -> bool {
+        matches!(x + 0, 0 | 2 | 4)
+    }
+
+    assert!(in_alternatives(-1));
+    assert!(in_alternatives(3));
+    assert!(!in_alternatives(0));
+}
+
+#[test]
+#[allow(deprecated)]
+fn converts_literals_next_to_union_definitions() {
+    #[replace_float_literals(literal as f32)]
+    mod bits {
+        pub union FloatBits {
+            pub float: f32,
+            pub bits: u32,
+        }
+
+        pub fn one_bits() -> u32 {
+            let value = FloatBits { float: 1.0 };
+            unsafe { value.bits }
+        }
+    }
+
+    assert_eq!(bits::one_bits(), 1.0f32.to_bits());
+
+    // A union on its own contains no literals to replace, which is reported as a warning
+    #[replace_numeric_literals(literal as f32)]
+    #[allow(dead_code)]
+    union IntOrFloat {
+        int: u32,
+        float: f32,
+    }
+
+    assert_eq!(std::mem::size_of::<IntOrFloat>(), 4);
+}
+
+#[test]
+fn converts_json_values() {
+    use serde_json::json;
+
+    #[replace_numeric_literals(literal as f64 / 2.0)]
+    fn config() -> serde_json::Value {
+        json!({
+            "tolerance": 1e-6,
+            "iterations": 100,
+            "solver": {
+                "name": "cg",
+                "weights": [1, -0.5, 3],
+            },
+        })
+    }
+
+    assert_eq!(
+        config(),
+        json!({
+            "tolerance": 5e-7,
+            "iterations": 50.0,
+            "solver": {
+                "name": "cg",
+                "weights": [0.5, -0.25, 1.5],
+            },
+        })
+    );
+}
+
+#[test]
+fn converts_only_arguments_of_formatting_macros() {
+    use std::fmt::Write;
+
+    #[replace_float_literals(literal * 2.0)]
+    fn formatted(y: f64) -> String {
+        format!("{x} {} {y}", 0.5, x = 1.5)
+    }
+
+    assert_eq!(formatted(0.25), "3 1 0.25");
+
+    // The destination of `write!` is left untouched, so its index is not replaced
+    #[replace_numeric_literals(literal as f64 * 2.0)]
+    fn written() -> Vec<String> {
+        let mut outputs = vec![String::new()];
+        write!(outputs[0], "{}", 2).unwrap();
+        writeln!(outputs[0], " {value}", value = 0.25).unwrap();
+        outputs
+    }
+
+    assert_eq!(written(), vec!["4 0.5\n"]);
+
+    // Format specs next to the argument do not change what is replaced
+    #[replace_float_literals(literal as i32)]
+    fn truncated() -> Vec<String> {
+        vec![
+            format!("{}", 3.25),
+            format!("{:.2}", 1.5),
+            format!("{x:>3}", x = 2.5),
+            format!("{}", "2.5"),
+        ]
+    }
+
+    assert_eq!(truncated(), vec!["3", "1", "  2", "2.5"]);
+}
+
+#[test]
+fn accepts_skipped_macros_without_literals_with_error_fallback() {
+    macro_rules! field_name {
+        ($field:ident : $ty:ty) => {
+            stringify!($field)
+        };
+    }
+
+    #[replace_float_literals(literal * 2.0, macro_fallback = error)]
+    fn gen() -> (Vec<f64>, &'static str) {
+        (vec![1.0, 2.0], field_name!(x: f64))
+    }
+
+    assert_eq!(gen(), (vec![2.0, 4.0], "x"));
+}
+
+#[test]
+fn uses_module_replacement_as_default() {
+    #[replace_numeric_literals(literal as f64)]
+    mod defaults {
+        use numeric_literals::{replace_int_literals, replace_numeric_literals};
+
+        pub fn half() -> f64 {
+            0.5
+        }
+
+        // Items with their own attribute are only converted by that attribute
+        #[replace_numeric_literals(literal as f32)]
+        pub fn quarter() -> f32 {
+            0.25
+        }
+
+        pub struct Scale;
+
+        impl Scale {
+            pub fn double(x: f64) -> f64 {
+                x * 2
+            }
+
+            #[replace_int_literals(literal as u8)]
+            pub fn bits() -> u8 {
+                8
+            }
+        }
+    }
+
+    assert_eq!(defaults::half(), 0.5f64);
+    assert_eq!(defaults::quarter(), 0.25f32);
+    assert_eq!(defaults::Scale::double(1.5), 3.0);
+    assert_eq!(defaults::Scale::bits(), 8u8);
+}
+
+#[test]
+fn applies_inner_attributes_to_module_items() {
+    mod defaults {
+        use num::Float;
+        use numeric_literals::replace_float_literals;
+
+        numeric_literals::apply_to_module! {
+            #![replace_float_literals(T::from(literal).unwrap())]
+            #![replace_int_literals(literal as f64 * 2.0)]
+
+            pub fn half<T: Float>() -> T {
+                0.5
+            }
+
+            pub fn doubled() -> f64 {
+                3
+            }
+
+            // Items with their own attribute are only converted by that attribute
+            #[replace_float_literals(literal as f32)]
+            pub fn quarter() -> f32 {
+                0.25
+            }
+        }
+    }
+
+    assert_eq!(defaults::half::<f32>(), 0.5);
+    assert_eq!(defaults::doubled(), 6.0);
+    assert_eq!(defaults::quarter(), 0.25f32);
+}
+
+#[test]
+fn respects_nested_attributes() {
+    #[replace_numeric_literals(literal as f64)]
+    fn gen() -> (f64, f32) {
+        #[replace_float_literals(literal as f32 / 2.0)]
+        fn inner() -> f32 {
+            0.5
+        }
+
+        (0.5, inner())
+    }
+
+    assert_eq!(gen(), (0.5, 0.25));
+
+    // Fully qualified paths and renamed imports within the decorated item are recognized
+    #[replace_numeric_literals(literal as f64)]
+    mod renamed {
+        use numeric_literals::replace_float_literals as halved;
+
+        #[halved(literal as f32 / 2.0)]
+        pub fn half() -> f32 {
+            1.0
+        }
+
+        #[numeric_literals::replace_int_literals(literal as u8)]
+        pub fn bits() -> u8 {
+            8
+        }
+    }
+
+    assert_eq!(renamed::half(), 0.5f32);
+    assert_eq!(renamed::bits(), 8u8);
+}
+
+#[test]
+fn converts_with_num_traits() {
+    use num::NumCast;
+
+    #[replace_float_literals(<T as NumCast>::from(literal).unwrap())]
+    #[replace_int_literals(T::zero() + <T as NumCast>::from(literal).unwrap())]
+    fn smoothstep<T: Float + NumCast>(x: T) -> T {
+        let t = x.max(0.0).min(1.0);
+        t * t * (3 - 2 * t)
+    }
+
+    assert_eq!(smoothstep(-1.0f32), 0.0);
+    assert_eq!(smoothstep(0.5f32), 0.5);
+    assert_eq!(smoothstep(0.25f64), 0.15625);
+    assert_eq!(smoothstep(2.0f64), 1.0);
+}
+
+#[test]
+fn converts_to_fixed_point() {
+    use fixed::types::extra::U16;
+    use fixed::FixedI32;
+
+    // The negation is applied after the conversion, so -0.0 is zero
+    #[replace_float_literals(FixedI32::<U16>::from_num(literal))]
+    fn gen() -> [FixedI32<U16>; 4] {
+        [-1.5, 0.0, -0.0, 32767.99998474121]
+    }
+
+    assert_eq!(
+        gen(),
+        [
+            FixedI32::<U16>::from_bits(-0x18000),
+            FixedI32::ZERO,
+            FixedI32::ZERO,
+            FixedI32::MAX
+        ]
+    );
+
+    #[replace_float_literals(FixedI32::<U16>::from_num(literal))]
+    fn scaled(x: FixedI32<U16>) -> FixedI32<U16> {
+        -0.5 * x + 0.25
+    }
+
+    assert_eq!(
+        scaled(FixedI32::from_num(3)),
+        FixedI32::<U16>::from_num(-1.25)
+    );
+}
+
+#[test]
+fn converts_to_non_copy_type() {
+    // Like `rug::Float`, the replacement is neither `Copy` nor `Clone`
+    #[derive(Debug, PartialEq)]
+    struct Boxed(Box<f64>);
+
+    impl Add for Boxed {
+        type Output = Boxed;
+
+        fn add(self, rhs: Boxed) -> Boxed {
+            Boxed(Box::new(*self.0 + *rhs.0))
+        }
+    }
+
+    impl Add<&Boxed> for Boxed {
+        type Output = Boxed;
+
+        fn add(self, rhs: &Boxed) -> Boxed {
+            Boxed(Box::new(*self.0 + *rhs.0))
+        }
+    }
+
+    #[replace_float_literals(Boxed(Box::new(literal)))]
+    fn gen(x: &Boxed) -> Boxed {
+        0.5 + 1.5 + x + x
+    }
+
+    assert_eq!(gen(&Boxed(Box::new(1.0))), Boxed(Box::new(4.0)));
+}
+
+#[test]
+fn applies_stacked_attributes_to_written_literals() {
+    // Each replacement contains literals that the other attributes would replace
+    #[replace_float_literals(literal + 10 as f64)]
+    #[replace_int_literals(literal as f64 + 100.0)]
+    fn float_int() -> (f64, f64, Vec<f64>) {
+        (1.5, 2, vec![1.5, 2])
+    }
+
+    #[replace_int_literals(literal as f64 + 100.0)]
+    #[replace_float_literals(literal + 10 as f64)]
+    fn int_float() -> (f64, f64, Vec<f64>) {
+        (1.5, 2, vec![1.5, 2])
+    }
+
+    assert_eq!(float_int(), (11.5, 102.0, vec![11.5, 102.0]));
+    assert_eq!(int_float(), (11.5, 102.0, vec![11.5, 102.0]));
+
+    // Literals are replaced by the first attribute that applies to them
+    #[replace_numeric_literals((literal as f64 + 1000.0) * 1 as f64)]
+    #[replace_float_literals(literal + 10 as f64)]
+    fn numeric_float() -> (f64, f64) {
+        (1.5, 2)
+    }
+
+    #[replace_float_literals(literal + 10 as f64)]
+    #[replace_numeric_literals((literal as f64 + 1000.0) * 1 as f64)]
+    fn float_numeric() -> (f64, f64) {
+        (1.5, 2)
+    }
+
+    #[replace_numeric_literals((literal as f64 + 1000.0) * 1 as f64)]
+    #[replace_int_literals(literal as f64 + 100.0)]
+    fn numeric_int() -> (f64, f64) {
+        (1.5, 2)
+    }
+
+    #[replace_int_literals(literal as f64 + 100.0)]
+    #[replace_numeric_literals((literal as f64 + 1000.0) * 1 as f64)]
+    fn int_numeric() -> (f64, f64) {
+        (1.5, 2)
+    }
+
+    assert_eq!(numeric_float(), (1001.5, 1002.0));
+    assert_eq!(float_numeric(), (11.5, 1002.0));
+    assert_eq!(numeric_int(), (1001.5, 1002.0));
+    assert_eq!(int_numeric(), (1001.5, 102.0));
+}
+
+#[test]
+fn applies_stacked_attributes_to_method_arguments_of_written_literals() {
+    // The exponent of `powi` is an integer literal of the float replacement, which the integer
+    // replacement must not convert in either order
+    #[replace_float_literals(T::from(literal).unwrap().powi(2))]
+    #[replace_int_literals(T::from(literal).unwrap())]
+    fn float_int<T: Float>() -> (T, T) {
+        (1.5, 3)
+    }
+
+    #[replace_int_literals(T::from(literal).unwrap())]
+    #[replace_float_literals(T::from(literal).unwrap().powi(2))]
+    fn int_float<T: Float>() -> (T, T) {
+        (1.5, 3)
+    }
+
+    assert_eq!(float_int::<f64>(), (2.25, 3.0));
+    assert_eq!(int_float::<f64>(), (2.25, 3.0));
+}
+
+#[test]
+fn applies_three_stacked_attributes_in_order() {
+    #[replace_numeric_literals((literal as f64 + 1000.0) * 1 as f64)]
+    #[replace_float_literals(literal + 10 as f64)]
+    #[replace_int_literals(literal as f64 + 100.0)]
+    fn numeric_float_int() -> (f64, f64) {
+        (1.5, 2)
+    }
+
+    #[replace_numeric_literals((literal as f64 + 1000.0) * 1 as f64)]
+    #[replace_int_literals(literal as f64 + 100.0)]
+    #[replace_float_literals(literal + 10 as f64)]
+    fn numeric_int_float() -> (f64, f64) {
+        (1.5, 2)
+    }
+
+    #[replace_float_literals(literal + 10 as f64)]
+    #[replace_numeric_literals((literal as f64 + 1000.0) * 1 as f64)]
+    #[replace_int_literals(literal as f64 + 100.0)]
+    fn float_numeric_int() -> (f64, f64) {
+        (1.5, 2)
+    }
+
+    #[replace_float_literals(literal + 10 as f64)]
+    #[replace_int_literals(literal as f64 + 100.0)]
+    #[replace_numeric_literals((literal as f64 + 1000.0) * 1 as f64)]
+    fn float_int_numeric() -> (f64, f64) {
+        (1.5, 2)
+    }
+
+    #[replace_int_literals(literal as f64 + 100.0)]
+    #[replace_numeric_literals((literal as f64 + 1000.0) * 1 as f64)]
+    #[replace_float_literals(literal + 10 as f64)]
+    fn int_numeric_float() -> (f64, f64) {
+        (1.5, 2)
+    }
+
+    #[replace_int_literals(literal as f64 + 100.0)]
+    #[replace_float_literals(literal + 10 as f64)]
+    #[replace_numeric_literals((literal as f64 + 1000.0) * 1 as f64)]
+    fn int_float_numeric() -> (f64, f64) {
+        (1.5, 2)
+    }
+
+    assert_eq!(numeric_float_int(), (1001.5, 1002.0));
+    assert_eq!(numeric_int_float(), (1001.5, 1002.0));
+    assert_eq!(float_numeric_int(), (11.5, 1002.0));
+    assert_eq!(float_int_numeric(), (11.5, 102.0));
+    assert_eq!(int_numeric_float(), (1001.5, 102.0));
+    assert_eq!(int_float_numeric(), (11.5, 102.0));
+}
+
+#[test]
+fn accepts_expected_number_of_replacements() {
+    // Literals in macro invocations are counted, while ignored literals are not
+    #[replace_numeric_literals(literal as f64, expect_replacements = 4, ignore_suffixed = true)]
+    fn gen() -> (f64, Vec<f64>, f64, u8) {
+        (1, vec![2.5, 3], 4.5, 5u8)
+    }
+
+    assert_eq!(gen(), (1.0, vec![2.5, 3.0], 4.5, 5));
+}
+
+#[test]
+#[allow(deprecated)]
+fn traces_replacements() {
+    // Tracing reports each replaced literal as a warning, but does not change the expansion
+    #[replace_numeric_literals(literal as f32, trace = true)]
+    fn gen() -> (f32, Vec<f32>) {
+        (1, vec![2.5, 3.0])
+    }
+
+    assert_eq!(gen(), (1.0, vec![2.5, 3.0]));
+}
+
+#[test]
+#[allow(deprecated)]
+fn leaves_items_unchanged_in_dry_run() {
+    // The would-be replacements are reported as warnings, and the literals keep their types
+    #[replace_numeric_literals(literal as f32, dry_run = true)]
+    fn gen() -> (i64, Vec<f64>) {
+        (1, vec![2.5, 3.0])
+    }
+
+    assert_eq!(gen(), (1, vec![2.5, 3.0]));
+}
+
+#[test]
+#[allow(deprecated)]
+fn warns_about_free_identifiers_in_replacements() {
+    // `scale` refers to the parameter of each function, which is reported unless it is listed
+    #[replace_float_literals(literal * scale)]
+    fn scaled(scale: f64) -> f64 {
+        2.0 + 0.5
+    }
+
+    #[replace_float_literals(literal * scale, captures = [scale])]
+    fn captured(scale: f64) -> f64 {
+        2.0 + 0.5
+    }
+
+    // Closure parameters, placeholders and called functions are not free
+    #[replace_float_literals((|x: f64| x * 2.0)(f64::abs(literal)))]
+    fn bound() -> f64 {
+        -1.5
+    }
+
+    assert_eq!(scaled(2.0), 5.0);
+    assert_eq!(captured(2.0), 5.0);
+    assert_eq!(bound(), -3.0);
+}
+
+#[test]
+#[allow(deprecated, clippy::no_effect)]
+fn warns_about_suspicious_replacements() {
+    // Without the placeholder, every literal is replaced with the same value, which is only
+    // reported with `strict = true`
+    #[replace_float_literals(1.5)]
+    fn constant() -> f64 {
+        0.5 + 2.0
+    }
+
+    // A replacement that discards the literal is reported as well
+    #[replace_int_literals({ literal; })]
+    fn discarded() -> Vec<()> {
+        vec![3, 4]
+    }
+
+    // A constant is intended for attributes that only replace literals of a single value
+    #[replace_zero_literals(T::zero())]
+    fn zero<T: num::Zero>() -> T {
+        0
+    }
+
+    assert_eq!(constant(), 3.0);
+    assert_eq!(discarded().len(), 2);
+    assert_eq!(zero::<f64>(), 0.0);
+}
+
+#[test]
+#[allow(deprecated, dead_code)]
+fn accepts_items_without_expressions() {
+    // The attribute has no effect on these items, which is reported as a warning
+    #[replace_float_literals(literal as f32)]
+    struct Unit;
+
+    #[replace_int_literals(literal as usize)]
+    type Alias = f64;
+
+    #[replace_numeric_literals(literal)]
+    trait Empty {}
+
+    // Array lengths are expressions, so the attribute has an effect here
+    #[replace_int_literals(literal as usize)]
+    struct Triple([f64; 3]);
+
+    assert_eq!(
+        std::mem::size_of::<Triple>(),
+        3 * std::mem::size_of::<Alias>()
+    );
+}
+
+#[test]
+fn converts_dbg_arguments() {
+    // `dbg!` returns its arguments, so the returned values are the printed ones
+    #[replace_float_literals(literal * 2.0)]
+    fn debugged(x: f64) -> (f64, (f64, f64), f64) {
+        (dbg!(1.5), dbg!(1.5, 2.5), dbg!(x + 1.5))
+    }
+
+    assert_eq!(debugged(1.0), (3.0, (3.0, 5.0), 4.0));
+}
+
+#[test]
+fn applies_parameters_alike_in_all_macros() {
+    // Float and integer literals are numbered and filtered the same way by all macros
+    #[replace_numeric_literals(literal_index as f64, ignore_suffixed = true)]
+    fn numeric() -> Vec<f64> {
+        vec![1.5, 2.0f64, 3, -4, f64::from(5u8)]
+    }
+
+    #[replace_float_literals(literal_index as f64, ignore_suffixed = true)]
+    fn float() -> Vec<f64> {
+        vec![1.5, 2.0f64, -3.5, 4.5]
+    }
+
+    #[replace_int_literals(literal_index as f64, ignore_suffixed = true)]
+    fn int() -> Vec<f64> {
+        vec![7, -8, f64::from(5u8)]
+    }
+
+    assert_eq!(numeric(), vec![0.0, 2.0, 1.0, -2.0, 5.0]);
+    assert_eq!(float(), vec![0.0, 2.0, -1.0, 2.0]);
+    assert_eq!(int(), vec![0.0, -1.0, 5.0]);
+}
+
+#[test]
+fn converts_expression_lists_with_either_separator() {
+    macro_rules! sum {
+        ($($x:expr),* $(,)?) => { 0.0 $(+ $x)* };
+        ($($x:expr);* $(;)?) => { 0.0 $(- $x)* };
+        ($x:expr, $y:expr; $z:expr) => { $x + $y + $z };
+    }
+
+    // A single expression and a list may both end with a separator
+    #[replace_float_literals(literal * 2.0)]
+    fn separated() -> Vec<f64> {
+        assert!(1.5 > 1.0,);
+        vec![sum!(0.5,), sum!(0.5, 1.0,), sum!(0.5; 1.0;)]
+    }
+
+    assert_eq!(separated(), vec![1.0, 3.0, -3.0]);
+
+    // Lists with mixed separators are not parsed, so their literals are not replaced
+    #[replace_float_literals(literal * 2.0)]
+    fn mixed() -> f64 {
+        sum!(0.5, 1.0; 2.0)
+    }
+
+    assert_eq!(mixed(), 3.5);
+}
+
+#[test]
+fn uses_registered_macro_parsers() {
+    macro_rules! entries {
+        ($($key:ident => $value:expr),* $(,)?) => { vec![$((stringify!($key), $value)),*] };
+    }
+    macro_rules! unscaled {
+        ($x:expr) => {
+            $x
+        };
+    }
+    macro_rules! say {
+        ($($t:tt)*) => { format!($($t)*) };
+    }
+
+    #[replace_float_literals(
+        literal * 2.0,
+        macro_parsers = [entries = tokens, unscaled = skip, say = format]
+    )]
+    fn gen() -> (Vec<(&'static str, f64)>, f64, String) {
+        (
+            entries!(a => 1.5, b => 2.5),
+            unscaled!(1.5),
+            say!("{:.1} {}", 1.5, "2.5"),
+        )
+    }
+
+    assert_eq!(
+        gen(),
+        (vec![("a", 3.0), ("b", 5.0)], 1.5, String::from("3.0 2.5"))
+    );
+}
+
+#[test]
+fn hoists_duplicate_literals() {
+    use std::cell::Cell;
+
+    /// Counts the conversions, and is not `Copy`
+    #[derive(Debug, PartialEq)]
+    struct Counted(f64);
+
+    impl Counted {
+        fn new(value: f64, conversions: &Cell<usize>) -> Self {
+            conversions.set(conversions.get() + 1);
+            Counted(value)
+        }
+    }
+
+    impl Clone for Counted {
+        fn clone(&self) -> Self {
+            Counted(self.0)
+        }
+    }
+
+    // Each distinct literal is converted once per call, except in closures, where literals are
+    // replaced in place
+    #[replace_numeric_literals(
+        Counted::new(literal as f64, conversions),
+        hoist = true,
+        captures = [conversions]
+    )]
+    fn values(conversions: &Cell<usize>) -> (Vec<Counted>, Vec<Counted>) {
+        let closure = || vec![4.0, 4.0];
+        let listed = vec![4.0, 4.0, 2.0, 4.0, 2.0, 4.];
+        (listed, closure())
+    }
+
+    let conversions = Cell::new(0);
+    let (listed, from_closure) = values(&conversions);
+    let expected: Vec<_> = [4.0, 4.0, 2.0, 4.0, 2.0, 4.0]
+        .iter()
+        .map(|v| Counted(*v))
+        .collect();
+    assert_eq!(listed, expected);
+    assert_eq!(from_closure, vec![Counted(4.0), Counted(4.0)]);
+    assert_eq!(conversions.get(), 3 + 2);
+
+    // Array lengths are const contexts, so they are replaced in place
+    #[replace_int_literals(literal as usize, hoist = true)]
+    fn lengths() -> usize {
+        let array = [3; 2];
+        array.len() + 2 + 2
+    }
+
+    assert_eq!(lengths(), 6);
+
+    // The bindings cannot collide with variables of the function, even of the same name
+    #[replace_float_literals(literal * 10.0, hoist = true)]
+    fn shadowed() -> (f64, f64) {
+        let __numeric_literal_2p0 = 1.0;
+        (2.0, __numeric_literal_2p0)
+    }
+
+    assert_eq!(shadowed(), (20.0, 10.0));
+}
+
+#[test]
+fn respects_visit_format_args() {
+    fn half(x: f64) -> f64 {
+        x / 2.0
+    }
+
+    // Positional format specs are part of the format string, which is never visited
+    #[replace_float_literals(literal * 2.0)]
+    fn visited() -> String {
+        format!("{1} {0}", half(1.5), 2.5)
+    }
+
+    assert_eq!(visited(), "5 1.5");
+
+    #[replace_float_literals(literal * 2.0, visit_format_args = false)]
+    fn unvisited() -> (String, f64) {
+        (format!("{1} {0}", half(1.5), 2.5), half(1.5))
+    }
+
+    assert_eq!(unvisited(), (String::from("2.5 0.75"), 1.5));
+}
+
+#[test]
+fn respects_visit_unsafe() {
+    // Literals in unsafe blocks, including those in macros, are offsets rather than numbers
+    #[replace_int_literals(T::from(literal).unwrap(), visit_unsafe = false)]
+    fn second_plus_one<T: Float>(values: &[T]) -> (T, Vec<usize>) {
+        let (second, offsets) = unsafe {
+            let offsets = vec![1, 2];
+            (*values.as_ptr().add(offsets[0]), offsets)
+        };
+        (second + 1, offsets)
+    }
+
+    assert_eq!(second_plus_one(&[1.5, 2.5]), (3.5, vec![1, 2]));
+}
+
+#[test]
+fn expands_presets() {
+    use num::FromPrimitive;
+
+    #[replace_numeric_literals(preset = "from_primitive")]
+    fn from_primitive<T: FromPrimitive + std::ops::Add<Output = T>>() -> T {
+        1.5 + 2
+    }
+
+    #[replace_float_literals(preset = "from", generic = S)]
+    fn from<S: From<f32>>() -> S {
+        2.5f32
+    }
+
+    #[replace_int_literals(preset = "as", generic = f64, visit_macros = false)]
+    fn cast() -> (f64, Vec<i32>) {
+        (-4, vec![1, 2])
+    }
+
+    assert_eq!(from_primitive::<f64>(), 3.5);
+    assert_eq!(from::<f64>(), 2.5);
+    assert_eq!(cast(), (-4.0, vec![1, 2]));
+}
+
+#[test]
+fn converts_debug_assertions() {
+    // Debug assertions are only checked in debug builds, which is what the tests run in
+    #[replace_float_literals(literal as i32)]
+    fn asserted() {
+        debug_assert!(1.1 == 1);
+        debug_assert_eq!(2.6, 2);
+        debug_assert_ne!(3.0, 4);
+    }
+
+    asserted();
+}
+
+#[test]
+fn attaches_suffixes_of_bare_primitive_types() {
+    #[replace_float_literals(f32)]
+    const fn halves() -> [f32; 2] {
+        [0.5, 1.5e0]
+    }
+
+    #[replace_numeric_literals(f64)]
+    fn mixed() -> f64 {
+        let x = 1.5 + 2;
+        x * 3f32
+    }
+
+    // Array lengths and patterns only admit literals, so they are suffixed as well
+    #[replace_int_literals(usize)]
+    fn classify(x: usize) -> [usize; 3] {
+        match x {
+            0 => [0; 3],
+            1..=9 => [x, 0xf, 1_000 - 999],
+            _ => [x; 3],
+        }
+    }
+
+    #[replace_int_literals(i8)]
+    fn extremes() -> (i8, i8) {
+        (-128, 127)
+    }
+
+    assert_eq!(halves(), [0.5, 1.5]);
+    assert_eq!(mixed(), 10.5);
+    assert_eq!(classify(0), [0, 0, 0]);
+    assert_eq!(classify(5), [5, 15, 1]);
+    assert_eq!(classify(12), [12, 12, 12]);
+    assert_eq!(extremes(), (-128, 127));
+}
+
+#[test]
+fn calls_bare_function_paths_with_literals() {
+    #[replace_float_literals(nalgebra::convert)]
+    fn converted<T: nalgebra::RealField>() -> T {
+        T::one() * 2.5
+    }
+
+    #[replace_numeric_literals(T::from_f64)]
+    fn method<T: num::FromPrimitive>() -> (Option<T>, Option<T>) {
+        (1.5, 4.0)
+    }
+
+    // A literal placeholder is still replaced with the literal
+    #[replace_float_literals(literal)]
+    fn identity() -> f64 {
+        1.25
+    }
+
+    assert_eq!(converted::<f64>(), 2.5);
+    assert_eq!(method::<f32>(), (Some(1.5), Some(4.0)));
+    assert_eq!(identity(), 1.25);
+}
+
+#[test]
+fn converts_default_bodies_and_values_of_traits() {
+    // The signatures, bounds and generics keep their integer literals, which must remain `usize`
+    #[replace_numeric_literals(literal as f64 * 2.0)]
+    trait Kernel<const N: usize = 2>
+    where
+        [f64; 2]: Default,
+    {
+        type Weights: AsRef<[f64]>;
+
+        const SCALE: f64 = 1.5;
+
+        fn weights(&self) -> [f64; 2] {
+            [0.25, 1]
+        }
+
+        fn scaled(&self, x: [f64; 2]) -> f64
+        where
+            [f64; 3]: Default,
+        {
+            x.iter().sum::<f64>() * Self::SCALE
+        }
+
+        fn radius(&self) -> f64;
+    }
+
+    struct Box2;
+
+    impl Kernel for Box2 {
+        type Weights = [f64; 2];
+
+        fn radius(&self) -> f64 {
+            1.0
+        }
+    }
+
+    // Default method bodies in terms of `Self`, as with a numeric trait bound on the implementor
+    #[replace_float_literals(Self::from_f64(literal).unwrap())]
+    trait Weight: num::FromPrimitive {
+        fn weight(&self) -> Self {
+            0.25
+        }
+    }
+
+    impl Weight for f32 {}
+
+    assert_eq!(Box2::SCALE, 3.0);
+    assert_eq!(Box2.weights(), [0.5, 2.0]);
+    assert_eq!(Box2.scaled([1.0, 2.0]), 9.0);
+    assert_eq!(Box2.radius(), 1.0);
+    assert_eq!(1.0f32.weight(), 0.25);
+}
+
+#[test]
+#[allow(deprecated)]
+fn leaves_extern_blocks_unchanged() {
+    #[replace_numeric_literals(literal as f32)]
+    extern "C" {
+        fn fabs(x: f64) -> f64;
+    }
+
+    assert_eq!(unsafe { fabs(-2.5) }, 2.5);
+}
+
+#[test]
+fn converts_all_items_of_impl_blocks() {
+    #[derive(Debug, PartialEq)]
+    struct Circle<T> {
+        radius: T,
+    }
+
+    #[replace_float_literals(T::from(literal).unwrap())]
+    impl<T: Float> Circle<T> {
+        fn unit() -> Self {
+            Circle { radius: 1.0 }
+        }
+
+        fn diameter(&self) -> T {
+            2.0 * self.radius
+        }
+
+        fn grown(self) -> Self {
+            Circle {
+                radius: self.radius + 0.5,
+            }
+        }
+    }
+
+    // Associated constants require a replacement that can be evaluated in const contexts
+    #[replace_float_literals(literal as f32)]
+    impl Circle<f32> {
+        const HALF: Circle<f32> = Circle { radius: 0.5 };
+        const AREA_FACTOR: f32 = 3.25;
+    }
+
+    assert_eq!(Circle::<f64>::unit(), Circle { radius: 1.0 });
+    assert_eq!(Circle { radius: 1.5f32 }.diameter(), 3.0);
+    assert_eq!(
+        Circle::<f64>::unit().grown().grown(),
+        Circle { radius: 2.0 }
+    );
+    assert_eq!(Circle::HALF.diameter(), 1.0);
+    assert_eq!(Circle::<f32>::AREA_FACTOR, 3.25);
+}
+
+#[test]
+fn converts_let_else_and_inline_const() {
+    // The pattern of `let ... else` is left untouched, while the expression and the diverging
+    // block are visited
+    #[replace_int_literals(T::from(literal).unwrap())]
+    fn first_or_zero<T: Float>(values: &[T], digit: usize) -> T {
+        let Some(first) = values.first() else {
+            return 0;
+        };
+        let 0..=9 = digit else { return 10 };
+        *first + 1
+    }
+
+    #[replace_float_literals(literal as f32, visit_inline_const = true)]
+    fn constant() -> f32 {
+        const { 0.5 * 3.0 }
+    }
+
+    assert_eq!(first_or_zero::<f64>(&[], 1), 0.0);
+    assert_eq!(first_or_zero(&[2.5], 1), 3.5);
+    assert_eq!(first_or_zero(&[2.5], 12), 10.0);
+    assert_eq!(constant(), 1.5);
+}
+
+#[test]
+fn converts_const_generic_defaults_and_arguments() {
+    struct Buffer<const N: usize> {
+        data: [u8; N],
+    }
+
+    // Replaced defaults and arguments are enclosed in braces, as required for expressions there
+    #[replace_int_literals(literal * 4)]
+    struct Scaled<const N: usize = 16> {
+        data: [u8; N],
+        inner: Buffer<2>,
+    }
+
+    #[replace_int_literals(usize)]
+    struct Suffixed<const N: usize = 16> {
+        data: [u8; N],
+    }
+
+    let scaled: Scaled = Scaled {
+        data: [0; 64],
+        inner: Buffer { data: [0; 8] },
+    };
+    let suffixed: Suffixed = Suffixed { data: [0; 16] };
+    assert_eq!(scaled.data.len(), 64);
+    assert_eq!(scaled.inner.data.len(), 8);
+    assert_eq!(suffixed.data.len(), 16);
+}
+
+#[test]
+fn converts_array_type_lengths() {
+    const N: usize = 4;
+
+    // A constant is inserted as it is, rather than called like a function
+    #[replace_int_literals(N)]
+    type Buf = [u8; 16];
+
+    #[replace_int_literals(literal * 2)]
+    type Doubled = [f64; 16];
+
+    #[replace_int_literals(literal * N)]
+    type Scaled = [u8; 2];
+
+    let buf: Buf = [0; 4];
+    let doubled: Doubled = [0.0; 32];
+    assert_eq!(buf.len(), 4);
+    assert_eq!(doubled.len(), 32);
+    assert_eq!(std::mem::size_of::<Scaled>(), 8);
+}
+
+#[test]
+fn leaves_inline_const_untouched_by_default() {
+    // The replacement cannot be evaluated in const contexts
+    #[replace_numeric_literals(num::cast::<_, f64>(literal).unwrap())]
+    fn doubled_halves() -> Vec<f64> {
+        let halves = [0.5; const { 1 + 2 }];
+        halves.iter().map(|&x| x * 2).collect()
+    }
+
+    assert_eq!(doubled_halves(), vec![1.0, 1.0, 1.0]);
+}
+
+#[test]
+fn leaves_const_generic_arguments_in_calls_untouched() {
+    struct Row([i32; 4]);
+
+    impl Row {
+        fn head<const N: usize>(&self) -> [i32; N] {
+            std::array::from_fn(|i| self.0[i])
+        }
+    }
+
+    fn repeated<const N: usize>(x: i32) -> [i32; N] {
+        [x; N]
+    }
+
+    #[replace_int_literals(T::from(literal).unwrap())]
+    fn heads<T: Float>(row: &Row) -> (Vec<T>, T) {
+        let ramp = std::array::from_fn::<_, 3, _>(|i| i as i32);
+        let head = row.head::<2>();
+        let repeated = repeated::<2>(ramp.len() as i32);
+        let sum = head.iter().chain(&repeated).sum::<i32>();
+        (vec![T::from(sum).unwrap(), 1], 2)
+    }
+
+    let (values, two) = heads::<f64>(&Row([10, 20, 30, 40]));
+    assert_eq!(values, vec![36.0, 1.0]);
+    assert_eq!(two, 2.0);
+}
+
+#[test]
+fn respects_visit_where_literals() {
+    trait Shape {
+        type Dims;
+        fn dims() -> Self::Dims;
+    }
+
+    struct Grid;
+
+    impl Shape for Grid {
+        type Dims = [usize; 4];
+        fn dims() -> [usize; 4] {
+            [3; 4]
+        }
+    }
+
+    #[replace_int_literals(literal * 2)]
+    fn rank<S>() -> usize
+    where
+        S: Shape<Dims = [usize; 2]>,
+    {
+        S::dims().len()
+    }
+
+    #[replace_int_literals(literal * 2, visit_where_literals = false)]
+    fn doubled_rank<S>() -> usize
+    where
+        S: Shape<Dims = [usize; 4]>,
+    {
+        S::dims().len() * 1
+    }
+
+    assert_eq!(rank::<Grid>(), 4);
+    assert_eq!(doubled_rank::<Grid>(), 8);
+}
+
+#[test]
+fn respects_visit_generic_args() {
+    use nalgebra::SMatrix;
+
+    const SCALE: usize = 2;
+
+    // The dimensions of the matrix type are replaced, in annotations and in paths
+    #[replace_int_literals(literal * SCALE)]
+    fn scaled_shapes() -> ((usize, usize), (usize, usize)) {
+        let annotated: SMatrix<f64, 3, 2> = SMatrix::zeros();
+        let called = SMatrix::<f64, 2, 3>::zeros();
+        (annotated.shape(), called.shape())
+    }
+
+    #[replace_int_literals(literal * SCALE, visit_generic_args = false)]
+    fn shapes() -> ((usize, usize), usize) {
+        let called = SMatrix::<f64, 2, 3>::zeros();
+        (called.shape(), 3)
+    }
+
+    assert_eq!(scaled_shapes(), ((6, 4), (4, 6)));
+    assert_eq!(shapes(), ((2, 3), 6));
+}
+
+#[test]
+fn respects_skip_shift_rhs() {
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Sample(i32);
+
+    impl From<i32> for Sample {
+        fn from(value: i32) -> Self {
+            Sample(value)
+        }
+    }
+
+    impl Add for Sample {
+        type Output = Sample;
+        fn add(self, other: Sample) -> Sample {
+            Sample(self.0 + other.0)
+        }
+    }
+
+    impl std::ops::Shr<u32> for Sample {
+        type Output = Sample;
+        fn shr(self, bits: u32) -> Sample {
+            Sample(self.0 >> bits)
+        }
+    }
+
+    impl std::ops::ShlAssign<u32> for Sample {
+        fn shl_assign(&mut self, bits: u32) {
+            self.0 <<= bits;
+        }
+    }
+
+    const BITS: u32 = 8;
+
+    // Shift amounts are left as machine integers, including nested expressions
+    #[replace_int_literals(Sample::from(literal), skip_shift_rhs = true)]
+    fn filtered(x: Sample) -> Sample {
+        let mut y = (x + 64) >> 3;
+        y <<= BITS - 7;
+        y + (x >> (BITS - 3)) + 1
+    }
+
+    assert_eq!(filtered(Sample(64)), Sample(35));
+}
+
+#[test]
+fn converts_struct_update_syntax() {
+    #[derive(Clone, Debug, PartialEq)]
+    struct Settings {
+        gain: f64,
+        offset: f64,
+        taps: usize,
+    }
+
+    let defaults = Settings {
+        gain: 1.0,
+        offset: 0.0,
+        taps: 4,
+    };
+
+    // The base is visited like any other expression: a plain identifier is left as it is, while
+    // literals in a computed base are replaced
+    #[replace_float_literals(literal * 2.0)]
+    fn updated(defaults: &Settings) -> (Settings, Settings) {
+        let plain = Settings {
+            gain: 3.0,
+            ..defaults.clone()
+        };
+        let computed = Settings {
+            taps: 8,
+            ..Settings {
+                offset: 0.25,
+                ..defaults.clone()
+            }
+        };
+        (plain, computed)
+    }
+
+    let (plain, computed) = updated(&defaults);
+    assert_eq!(
+        plain,
+        Settings {
+            gain: 6.0,
+            ..defaults.clone()
+        }
+    );
+    assert_eq!(
+        computed,
+        Settings {
+            gain: 1.0,
+            offset: 0.5,
+            taps: 8
+        }
+    );
+}
+
+#[test]
+fn respects_skip_ranges() {
+    // Endpoints of ranges remain integers, while the other literals are converted
+    #[replace_int_literals(T::from(literal).unwrap(), skip_ranges = true)]
+    fn weighted_sum<T: Float>(data: &[T]) -> T {
+        let mut sum = 0;
+        for i in 0..3 {
+            sum = sum + T::from(i).unwrap() * 2;
+        }
+        for x in &data[2..=3] {
+            sum = sum + *x;
+        }
+        sum + T::from(data[4..].len()).unwrap()
+    }
+
+    assert_eq!(weighted_sum(&[1.0, 2.0, 3.0, 4.0, 5.0]), 6.0 + 7.0 + 1.0);
+}
+
+#[test]
+fn accepts_indices_with_deny_index_replacement() {
+    // Every index is a suffixed literal or a range endpoint, so no literal in an index is replaced
+    #[replace_numeric_literals(T::from(literal).unwrap(), deny_index_replacement = true, ignore_suffixed = true, skip_ranges = true)]
+    fn weighted<T: Float>(data: &[T]) -> T {
+        data[0usize] * 2 + data[1..][0usize] * 0.5
+    }
+
+    assert_eq!(weighted(&[1.0, 4.0]), 4.0);
+}
+
+#[test]
+fn respects_arithmetic_context() {
+    // Replaced literals are scaled by 10, so that each row shows whether its literals are replaced
+    #[replace_int_literals(literal * 10, context = arithmetic)]
+    fn cases(x: i32, v: &[i32]) -> Vec<(&'static str, i32, i32)> {
+        let mut y = x;
+        y += 2;
+        vec![
+            ("operand of +", x + 1, 11),
+            ("left operand of -", 5 - x, 49),
+            ("negated operand", x * -2, -20),
+            ("operand in parentheses", x * (3), 30),
+            ("operand of %", 25 % (x + 1), 8),
+            ("operand of a comparison", (x < 5) as i32, 1),
+            ("compound assignment", y, 21),
+            ("literal operands", 1 + 2, 3),
+            ("method argument", x.max(5), 50),
+            ("method argument on a literal", 7i32.pow(2), 49),
+            ("index", v[1], 20),
+            ("repeat length", vec![x; 2].len() as i32, 2),
+            ("range endpoint", (0..3).len() as i32, 3),
+            ("shift amount", x << 1, 2),
+            ("function argument", i32::abs(-4), 4),
+        ]
+    }
+
+    for (case, actual, expected) in cases(1, &[10, 20, 30]) {
+        assert_eq!(actual, expected, "{}", case);
+    }
+}
+
+#[test]
+fn converts_struct_and_tuple_struct_initializers() {
+    #[derive(Debug, PartialEq)]
+    struct Point(f32, f32, f32);
+
+    #[derive(Debug, PartialEq)]
+    struct Segment {
+        start: Point,
+        length: f32,
+    }
+
+    #[replace_float_literals(literal as f32)]
+    fn segment() -> Segment {
+        Segment {
+            start: Point(1.0, 2.5, -3.0),
+            length: 0.5,
+        }
+    }
+
+    assert_eq!(
+        segment(),
+        Segment {
+            start: Point(1.0, 2.5, -3.0),
+            length: 0.5
+        }
+    );
+}
+
+#[test]
+fn respects_only_in_calls() {
+    // Only the arguments of `scale` and `mul_add` are converted, the other literals remain
+    fn scale<T: Float>(x: T, factor: T) -> T {
+        x * factor
+    }
+
+    #[replace_float_literals(T::from(literal).unwrap(), only_in_calls = [scale, mul_add])]
+    fn weighted<T: Float>(x: T, samples: [f64; 2]) -> T {
+        let offset = 1.5 + samples[0] * -2.0;
+        let y = scale(x, 2.0).mul_add(3.0, -0.5);
+        y + T::from(offset).unwrap() + T::from(samples[1] * 4.0).unwrap()
+    }
+
+    assert_eq!(weighted(1.0f32, [0.5, 0.25]), 5.5 + 0.5 + 1.0);
+}
+
+#[test]
+fn respects_skip_in_calls() {
+    // Counts and capacities remain `usize` at any depth in the arguments, the other literals are converted
+    #[replace_int_literals(T::from(literal).unwrap(), skip_in_calls = [with_capacity, take, skip])]
+    fn sum_middle<T: Float>(data: &[T]) -> T {
+        let mut values = Vec::with_capacity(2 * 2);
+        for x in data.iter().skip(1).take(data.len() - 2) {
+            values.push(*x * 2);
+        }
+        values.into_iter().fold(0, |sum, x| sum + x)
+    }
+
+    assert_eq!(sum_middle(&[1.0, 2.0, 3.0, 4.0]), 10.0);
+}
+
+#[test]
+fn respects_only_in_bindings() {
+    // Only the initializers of `weights` and `(shift, _)` are converted, indexing remains `usize`
+    #[replace_numeric_literals(T::from(literal).unwrap(), only_in_bindings = [weights, shift])]
+    fn weighted<T: Float>(data: &[T]) -> T {
+        let weights = [0.25, 0.75];
+        let (shift, unused) = (1.0, 2.0_f64);
+        let index = 1;
+        let Some(first) = data.first() else {
+            return T::from(unused).unwrap();
+        };
+        *first * weights[index - 1] + data[index] * weights[index] + shift
+    }
+
+    assert_eq!(weighted(&[4.0f64, 8.0]), 1.0 + 6.0 + 1.0);
+    assert_eq!(weighted::<f64>(&[]), 2.0);
+}
+
+#[test]
+fn converts_break_values() {
+    // The loops only exist to break with a value
+    #[replace_int_literals(literal as f64)]
+    #[allow(clippy::never_loop)]
+    fn first_above(data: &[f64], threshold: f64) -> (f64, f64) {
+        let mut values = data.iter();
+        let found = 'outer: loop {
+            loop {
+                match values.next() {
+                    None => break 'outer -1,
+                    Some(&x) if x > threshold => break 'outer 10 * x,
+                    Some(_) => {}
+                }
+            }
+        };
+        let remaining = loop {
+            break values.len() as f64 + 100;
+        };
+        (found, remaining)
+    }
+
+    assert_eq!(first_above(&[1.0, 3.0, 5.0], 2.0), (30.0, 101.0));
+    assert_eq!(first_above(&[1.0], 2.0), (-1.0, 100.0));
+}
+
+#[test]
+fn converts_transcribers_of_macro_rules() {
+    // Literals in the transcribers are converted, while the matchers keep their literal tokens
+    #[replace_float_literals(literal * 10.0)]
+    macro_rules! scaled {
+        (half $x:expr) => {
+            $x * 0.5
+        };
+        (0.5 $x:expr) => {
+            $x * 0.5
+        };
+        ($($x:expr),*) => {
+            [$($x * 2.0),*]
+        };
+    }
+
+    let x: f32 = 3.0;
+    assert_eq!(scaled!(half x), 15.0);
+    assert_eq!(scaled!(0.5 x), 15.0);
+    assert_eq!(scaled!(x, 1.0f32), [60.0, 20.0]);
+}
+
+#[test]
+fn converts_transcribers_of_macro_rules_without_fields_and_lengths() {
+    // Tuple fields in the transcribers are left untouched, and array lengths are not promoted
+    #[replace_float_literals(literal * 10.0, promote_ints = true)]
+    macro_rules! first {
+        ($x:expr) => {
+            [$x.0 + 1; 2]
+        };
+    }
+
+    let p: (f64, f64) = (3.0, 4.0);
+    assert_eq!(first!(p), [13.0, 13.0]);
+}
+
+#[test]
+fn leaves_nested_macro_rules_untouched() {
+    // Definitions inside an item with the attribute are not visited
+    #[replace_int_literals(literal as i64)]
+    fn first(p: (i64, i64)) -> i64 {
+        macro_rules! first {
+            ($x:expr) => {
+                $x.0 + [1; 4].len() as i64
+            };
+        }
+        first!(p)
+    }
+
+    assert_eq!(first((3, 4)), 7);
+}
+
+#[test]
+fn converts_labeled_blocks() {
+    #[replace_float_literals(T::from(literal).unwrap())]
+    fn clamped<T: Float>(x: T) -> (T, T) {
+        let clamped = 'clamp: {
+            if x < 0.0 {
+                break 'clamp 0.0;
+            }
+            if x > 1.0 {
+                break 'clamp 1.0;
+            }
+            x
+        };
+        // The final expression of a labeled block is converted like that of any block
+        #[allow(unused_labels)]
+        let half = 'half: { 0.5 };
+        (clamped, half)
+    }
+
+    assert_eq!(clamped(-2.0), (0.0, 0.5));
+    assert_eq!(clamped(4.0), (1.0, 0.5));
+    assert_eq!(clamped(0.25), (0.25, 0.5));
+}
+
+#[test]
+fn converts_return_values() {
+    fn halved<T: Float>(x: T) -> T {
+        x / T::from(2.0).unwrap()
+    }
+
+    #[replace_float_literals(T::from(literal).unwrap())]
+    fn clamped<T: Float>(x: T) -> T {
+        if x < 0.0 {
+            return 0.0;
+        }
+        if x > 10.0 {
+            return halved(20.5);
+        }
+        x
+    }
+
+    #[replace_float_literals(T::from(literal).unwrap())]
+    fn accumulate<T: Float>(x: T, out: &mut T) {
+        if x < 0.0 {
+            return;
+        }
+        *out = *out + x * 1.5;
+    }
+
+    assert_eq!(clamped(-1.0), 0.0);
+    assert_eq!(clamped(12.0), 10.25);
+    assert_eq!(clamped(4.0), 4.0);
+
+    let mut out = 1.0;
+    accumulate(-1.0, &mut out);
+    accumulate(2.0, &mut out);
+    assert_eq!(out, 4.0);
+}
+
+#[test]
+fn respects_promote_ints() {
+    // Unsuffixed integers are converted as floats, while suffixed integers and lengths remain
+    #[replace_float_literals(T::from(literal).unwrap(), promote_ints = true)]
+    fn weights<T: Float>(x: T) -> [T; 3] {
+        let mut weights = [0.5; 3];
+        weights[1usize] = x * 2 - 1;
+        weights[2usize] = T::from(0xA_usize).unwrap() / 4;
+        weights
+    }
+
+    assert_eq!(weights(1.5), [0.5, 2.0, 2.5]);
+}
+
+#[test]
+fn respects_fold_constants() {
+    // Subexpressions of float literals are folded into a single literal before it is replaced
+    #[replace_float_literals(literal_text, fold_constants = true)]
+    fn texts() -> Vec<&'static str> {
+        vec![1.0 / 3.0, 2.0 * 0.5 * 0.25, (0.5 + 0.25), 1.5]
+    }
+
+    // Folding does not cross other operands, and keeps the sign of the result
+    #[replace_float_literals(literal * 10.0, fold_constants = true)]
+    fn values(x: f64) -> (f64, f64, f64) {
+        (x * 2.0 * 0.5, f64::abs(1.0 - 3.0), -(0.5 + 0.5))
+    }
+
+    assert_eq!(texts(), vec!["0.3333333333333333", "0.25", "0.75", "1.5"]);
+    assert_eq!(values(1.0), (100.0, 20.0, -10.0));
+}
+
+#[test]
+fn leaves_offset_of_untouched() {
+    #[repr(C)]
+    struct Pair(u8, u32);
+
+    #[repr(C)]
+    struct Wrapper<T> {
+        tag: u8,
+        pair: Pair,
+        value: T,
+    }
+
+    // The tuple fields `1` are not replaced, and the generic type does not prevent the expansion
+    #[replace_int_literals(literal * 10)]
+    fn offsets() -> (usize, usize, usize) {
+        (
+            std::mem::offset_of!(Pair, 1) + 1,
+            std::mem::offset_of!(Wrapper<u64>, pair.1),
+            std::mem::offset_of!(Wrapper<u16>, tag),
+        )
+    }
+
+    assert_eq!(offsets(), (4 + 10, 4 + 4, 0));
+}
+
+#[test]
+fn respects_expansion_markers() {
+    // The binding of the marker does not shadow the variable of the same name
+    #[replace_float_literals(literal * 2.0, expansion_markers = true)]
+    fn values(__numeric_literal_replaced__: f64) -> (f64, [u8; 2]) {
+        const SCALE: f64 = 1.5;
+        (__numeric_literal_replaced__ + SCALE, [0; 2])
+    }
+
+    // The markers also work in constant contexts, such as the array length, which becomes 3
+    #[replace_int_literals(literal + 1, expansion_markers = true)]
+    const LENGTHS: [usize; 2] = [1, 2, 3];
+
+    assert_eq!(values(1.0), (4.0, [0; 2]));
+    assert_eq!(LENGTHS, [2, 3, 4]);
+}
+
+#[test]
+fn keeps_unsupported_syntax_as_written() {
+    // Raw borrows are kept as unparsed tokens, which pass through unchanged apart from literals
+    #[replace_float_literals(literal * 2.0)]
+    fn first(values: &[f64; 2]) -> (f64, f64) {
+        let first = &raw const values[0];
+        (unsafe { *first }, 1.5)
+    }
+
+    assert_eq!(first(&[4.0, 5.0]), (4.0, 3.0));
 }